@@ -0,0 +1,114 @@
+//! Sanity checks over imported time that flag likely mistakes without changing anything.
+
+use crate::config::{ContractConfig, ContractRate, ProjectConfig};
+use crate::{Money, TimeEntry};
+use chrono::{Duration, NaiveDate};
+use std::collections::{BTreeSet, HashMap};
+
+/// Pairs of project names that are probably the same project spelt differently
+///
+/// Names are compared case-insensitively with surrounding whitespace ignored,
+/// and count as similar when at most two edits apart, provided the edits are a
+/// small part of the name. Short names like "API" and "APP" are left alone.
+pub fn similar_project_names<'a>(names: &[&'a str]) -> Vec<(&'a str, &'a str)> {
+    let normalized: Vec<String> = names.iter().map(|n| n.trim().to_lowercase()).collect();
+
+    let mut pairs = Vec::new();
+    for i in 0..names.len() {
+        for j in i + 1..names.len() {
+            let (a, b) = (&normalized[i], &normalized[j]);
+            let distance = strsim::levenshtein(a, b);
+            let shorter = a.chars().count().min(b.chars().count());
+
+            if distance <= 2 && distance * 5 <= shorter {
+                pairs.push((names[i], names[j]));
+            }
+        }
+    }
+
+    pairs
+}
+
+/// Entries that were read but probably shouldn't have been: ones with no time,
+/// and ones for projects missing from the config file when it lists any
+pub fn suspicious_entries(
+    entries: &[TimeEntry],
+    projects: &HashMap<String, ProjectConfig>,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for entry in entries.iter().filter(|e| e.duration.is_zero()) {
+        problems.push(format!(
+            "Entry of \"{}\"{} has no time",
+            entry.project,
+            entry.date.map(|d| format!(" on {}", d)).unwrap_or_default()
+        ));
+    }
+
+    if !projects.is_empty() {
+        let unknown: BTreeSet<&str> = entries
+            .iter()
+            .map(|e| e.project.as_str())
+            .filter(|p| !projects.contains_key(*p))
+            .collect();
+        for project in unknown {
+            problems.push(format!(
+                "\"{}\" isn't a project in the config file",
+                project
+            ));
+        }
+    }
+
+    problems
+}
+
+/// Days before a contract's renewal date that invoices start warning about it
+const RENEWAL_NOTICE_DAYS: i64 = 30;
+
+/// Ways an invoice dated `date` at `pay_rate` falls outside its client's contract
+pub fn contract_problems(
+    contract: &ContractConfig,
+    pay_rate: Money,
+    date: NaiveDate,
+) -> Vec<String> {
+    let reference = contract.reference();
+    let mut problems = Vec::new();
+
+    if let Some(expires) = contract.expires.filter(|expires| date > *expires) {
+        problems.push(format!(
+            "The invoice is dated {}, after {} expired on {}",
+            date, reference, expires
+        ));
+    } else if let Some(renewal) = contract.renewal {
+        if date + Duration::days(RENEWAL_NOTICE_DAYS) >= renewal {
+            problems.push(format!("Renewal of {} is due on {}", reference, renewal));
+        }
+    }
+
+    if !contract.rates.is_empty() {
+        let rate = |r: &ContractRate| Money::new(r.rate, pay_rate.currency());
+        let valid: Vec<&ContractRate> = contract
+            .rates
+            .iter()
+            .filter(|r| r.from.is_none_or(|from| from <= date) && r.to.is_none_or(|to| date <= to))
+            .collect();
+
+        if valid.is_empty() {
+            problems.push(format!(
+                "None of the rates in {} are valid on {}",
+                reference, date
+            ));
+        } else if !valid.iter().any(|r| rate(r) == pay_rate) {
+            let rates: Vec<String> = valid.iter().map(|r| rate(r).per_hour()).collect();
+            problems.push(format!(
+                "The pay rate of {} doesn't match the {} agreed in {} for {}",
+                pay_rate.per_hour(),
+                rates.join(" or "),
+                reference,
+                date
+            ));
+        }
+    }
+
+    problems
+}
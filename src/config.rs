@@ -0,0 +1,389 @@
+//! The optional TOML configuration file.
+//!
+//! Settings that don't belong on the command line, such as API tokens, are
+//! read from `$XDG_CONFIG_HOME/pint-rs/config.toml` (usually
+//! `~/.config/pint-rs/config.toml`) or the file passed with `--config`:
+//!
+//! ```toml
+//! [caldav]
+//! url = "https://cloud.example.com/remote.php/dav/calendars/me/work/"
+//! username = "me"
+//! password = "..."
+//!
+//! [clockify]
+//! api_key = "..."
+//! workspace_id = "..."
+//!
+//! [google]
+//! client_id = "..."
+//! client_secret = "..."
+//! refresh_token = "..."
+//!
+//! [jira]
+//! url = "https://acme.atlassian.net"
+//! email = "me@example.com"
+//! api_token = "..."
+//!
+//! [toggl]
+//! api_token = "..."
+//! workspace_id = 123456
+//!
+//! [wakatime]
+//! api_key = "..."
+//!
+//! [supplier]
+//! name = "Bonnici Consulting"
+//! address = ["1 Main Street", "Valletta VLT 1000", "Malta"]
+//! tax_id = "MT12345678"
+//! email = "billing@example.com"
+//!
+//! [dates]
+//! locale = "fr_FR"
+//! format = "%-d %B %Y"
+//!
+//! [holidays]
+//! country = "MT"
+//! dates = ["2024-08-16"]
+//!
+//! [project."Acme Website v1"]
+//! archived = true
+//! successor = "Acme Website"
+//!
+//! [project."Acme Training"]
+//! tax_exempt = true
+//!
+//! [client.acme.details]
+//! name = "Acme Corporation"
+//! address = ["100 King Street West", "Toronto ON M5X 1A9", "Canada"]
+//!
+//! [client.acme]
+//! number_format = "ACME-{year}-{seq:3}"
+//! pay_rate = 110.0
+//! gst = 0.05
+//! currency = "CAD"
+//!
+//! [[client.acme.rates]]
+//! rate = 100.0
+//! from = "2024-06-15"
+//!
+//! [[client.acme.tiers]]
+//! hours = 20.0
+//! rate = 150.0
+//!
+//! [[client.acme.tiers]]
+//! rate = 120.0
+//!
+//! [[client.acme.items]]
+//! name = "Hosting"
+//! amount = 40.0
+//!
+//! [client.acme.minimum]
+//! amount = 500.0
+//! shortfall = "fee"
+//!
+//! [client.acme.contract]
+//! id = "MSA-2024-01"
+//! sow = "SOW-7"
+//! renewal = "2024-11-01"
+//! expires = "2024-12-31"
+//!
+//! [[client.acme.contract.rates]]
+//! rate = 95.0
+//! from = "2024-01-01"
+//! to = "2024-12-31"
+//! ```
+
+use crate::{Currency, GstRate, Party};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub caldav: Option<CaldavConfig>,
+    pub clockify: Option<ClockifyConfig>,
+    pub google: Option<GoogleConfig>,
+    pub jira: Option<JiraConfig>,
+    pub toggl: Option<TogglConfig>,
+    pub wakatime: Option<WakaTimeConfig>,
+    pub dates: Option<DatesConfig>,
+    /// Your business's details, printed at the top of every invoice
+    pub supplier: Option<Party>,
+    pub holidays: Option<HolidaysConfig>,
+    /// Per-client settings, chosen with `--client`
+    #[serde(default)]
+    pub client: HashMap<String, ClientConfig>,
+    /// Per-project settings, by the project's name as it's billed
+    #[serde(default)]
+    pub project: HashMap<String, ProjectConfig>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// The project is finished, so new time against it was probably tracked by mistake
+    #[serde(default)]
+    pub archived: bool,
+    /// The project an archived project's time is billed under instead
+    pub successor: Option<String>,
+    /// The project is tax exempt or zero-rated, so its amount isn't taxed
+    #[serde(default)]
+    pub tax_exempt: bool,
+}
+
+/// The public holidays `--holiday-multiplier` charges more for
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HolidaysConfig {
+    #[serde(default)]
+    pub dates: Vec<NaiveDate>,
+    /// A country whose public holidays are looked up too, e.g. "MT"
+    pub country: Option<String>,
+}
+
+/// How dates are written on the invoice
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DatesConfig {
+    /// The locale used when `--locale` isn't given, e.g. "fr_FR"
+    pub locale: Option<String>,
+    /// A strftime-style format such as "%d/%m/%Y"
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientConfig {
+    /// The client's name and address, printed as who the invoice is billed to
+    pub details: Option<Party>,
+    /// How the client's invoice numbers are written when `--number-format` isn't given
+    pub number_format: Option<String>,
+    /// The pay rate used when `--pay-rate` isn't given
+    pub pay_rate: Option<f64>,
+    /// The GST used when `--gst` isn't given, as a percentage or a fraction
+    pub gst: Option<GstRate>,
+    /// The currency used when `--currency` isn't given
+    pub currency: Option<Currency>,
+    pub contract: Option<ContractConfig>,
+    /// Rates that replace the pay rate from their effective date onwards
+    #[serde(default)]
+    pub rates: Vec<ScheduledRate>,
+    /// Blocks of hours charged at falling rates as more are billed
+    #[serde(default)]
+    pub tiers: Vec<PriceTier>,
+    pub minimum: Option<MinimumCharge>,
+    /// Fixed-price items put on every invoice for the client, like a retainer
+    #[serde(default)]
+    pub items: Vec<FixedItem>,
+}
+
+/// A fixed-price item charged besides the hours, such as a deliverable
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FixedItem {
+    pub name: String,
+    pub amount: f64,
+    /// The item isn't taxed, like a tax-exempt project
+    #[serde(default)]
+    pub tax_exempt: bool,
+}
+
+/// The least a client is invoiced for, as agreed in their contract
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MinimumCharge {
+    /// The minimum subtotal, before GST
+    pub amount: f64,
+    #[serde(default)]
+    pub shortfall: Shortfall,
+}
+
+/// What happens when the hours come to less than the minimum
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Shortfall {
+    /// Charge the difference as a minimum engagement fee
+    #[default]
+    Fee,
+    /// Don't invoice yet, so the hours are billed with next period's
+    Defer,
+}
+
+/// An hourly rate charged for entries dated on or after `from`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduledRate {
+    pub rate: f64,
+    pub from: NaiveDate,
+    /// The project the rate is for, or every project without a rate of its own
+    pub project: Option<String>,
+}
+
+/// A block of hours charged at its own rate, used up before the next tier's
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PriceTier {
+    /// How many hours the tier covers, or every hour left when it's the last
+    pub hours: Option<f64>,
+    pub rate: f64,
+    /// The project the tier is for, or every project without tiers of its own
+    pub project: Option<String>,
+}
+
+/// The agreement invoices for a client are raised under
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContractConfig {
+    pub id: Option<String>,
+    /// The statement of work the hours are billed against
+    pub sow: Option<String>,
+    /// The agreed hourly rates and the periods they apply to
+    #[serde(default)]
+    pub rates: Vec<ContractRate>,
+    /// When the contract needs renegotiating, warned about a month ahead
+    pub renewal: Option<NaiveDate>,
+    pub expires: Option<NaiveDate>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContractRate {
+    pub rate: f64,
+    /// The first day the rate applies, open-ended when left out
+    pub from: Option<NaiveDate>,
+    /// The last day the rate applies, open-ended when left out
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CaldavConfig {
+    /// The calendar collection to bill events from
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClockifyConfig {
+    pub api_key: String,
+    pub workspace_id: String,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GoogleConfig {
+    /// The OAuth client the refresh token was issued to
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub refresh_token: Option<String>,
+    /// The calendar to bill events from when `--google-calendar` isn't given
+    pub calendar: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JiraConfig {
+    pub url: Option<String>,
+    pub email: Option<String>,
+    pub api_token: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TogglConfig {
+    pub api_token: String,
+    pub workspace_id: u64,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WakaTimeConfig {
+    pub api_key: String,
+    /// The API to use instead of wakatime.com, for self-hosted servers
+    pub api_url: Option<String>,
+}
+
+impl CaldavConfig {
+    pub(crate) const EMPTY: Self = Self {
+        url: None,
+        username: None,
+        password: None,
+    };
+}
+
+impl GoogleConfig {
+    pub(crate) const EMPTY: Self = Self {
+        client_id: None,
+        client_secret: None,
+        refresh_token: None,
+        calendar: None,
+    };
+}
+
+impl JiraConfig {
+    pub(crate) const EMPTY: Self = Self {
+        url: None,
+        email: None,
+        api_token: None,
+    };
+}
+
+impl ContractConfig {
+    /// How the contract is referred to in warnings
+    pub fn reference(&self) -> String {
+        match (&self.id, &self.sow) {
+            (Some(id), Some(sow)) => format!("contract {} ({})", id, sow),
+            (Some(id), None) => format!("contract {}", id),
+            (None, Some(sow)) => format!("contract for {}", sow),
+            (None, None) => "the contract".to_owned(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the given config file, or the default one if it exists
+    ///
+    /// A missing default file is the same as an empty config, but a file
+    /// that was asked for explicitly has to exist.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_owned(),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read config file \"{:?}\"", path))?;
+
+        Self::parse(&contents)
+            .with_context(|| format!("Unable to parse config file \"{:?}\"", path))
+    }
+
+    /// Looks up the `[client.<name>]` section for a client
+    pub fn client(&self, name: &str) -> Result<&ClientConfig> {
+        self.client
+            .get(name)
+            .with_context(|| format!("No [client.{}] section in the config file", name))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// Where the config file is looked for when `--config` isn't given
+    pub fn default_path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+        Some(config_dir.join("pint-rs").join("config.toml"))
+    }
+}
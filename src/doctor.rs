@@ -0,0 +1,204 @@
+//! The checks behind `--doctor`, for when something misbehaves and it isn't
+//! clear whether the config file, the environment or a service is to blame.
+
+use crate::config::Config;
+use chrono::format::{Item, StrftimeItems};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+
+/// How long to wait for a service to accept a connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The outcome of one check
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub subject: String,
+    /// What's wrong, or nothing when the check passed
+    pub problem: Option<String>,
+}
+
+impl Check {
+    fn passed(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            problem: None,
+        }
+    }
+
+    fn failed(subject: impl Into<String>, problem: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            problem: Some(problem.into()),
+        }
+    }
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.problem {
+            None => write!(f, "ok    {}", self.subject),
+            Some(problem) => write!(f, "FAIL  {}: {}", self.subject, problem),
+        }
+    }
+}
+
+/// Runs every check, connecting to the services the config file sets up
+pub fn run(config_path: Option<&Path>) -> Vec<Check> {
+    let path = config_path
+        .map(Path::to_owned)
+        .or_else(Config::default_path);
+    let subject = match &path {
+        Some(path) => format!("Config file {}", path.display()),
+        None => "Config file".to_owned(),
+    };
+
+    let config = match Config::load(config_path) {
+        Ok(config) => config,
+        Err(err) => return vec![Check::failed(subject, format!("{:#}", err))],
+    };
+
+    let mut checks = vec![Check::passed(subject)];
+    checks.extend(config_checks(&config));
+    checks.extend(
+        endpoints(&config)
+            .into_iter()
+            .map(|(service, url)| match reachable(&url) {
+                Ok(()) => Check::passed(format!("{} at {}", service, url)),
+                Err(err) => Check::failed(format!("{} at {}", service, url), err),
+            }),
+    );
+
+    checks
+}
+
+/// Checks the settings that parse but can't be used
+pub fn config_checks(config: &Config) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    if let Some(dates) = &config.dates {
+        if let Some(locale) = &dates.locale {
+            checks.push(match crate::parse_locale(locale) {
+                Ok(_) => Check::passed(format!("Date locale {}", locale)),
+                Err(err) => Check::failed(format!("Date locale {}", locale), err.to_string()),
+            });
+        }
+        if let Some(format) = &dates.format {
+            checks.push(
+                match StrftimeItems::new(format).any(|item| item == Item::Error) {
+                    false => Check::passed(format!("Date format \"{}\"", format)),
+                    true => Check::failed(
+                        format!("Date format \"{}\"", format),
+                        "it isn't a valid strftime format",
+                    ),
+                },
+            );
+        }
+    }
+
+    let mut projects: Vec<_> = config.project.iter().collect();
+    projects.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, project) in projects {
+        let Some(successor) = &project.successor else {
+            continue;
+        };
+        let subject = format!("Successor of project \"{}\"", name);
+        checks.push(match config.project.get(successor) {
+            _ if successor == name => Check::failed(subject, "it succeeds itself"),
+            Some(next) if next.archived => Check::failed(
+                subject,
+                format!(
+                    "\"{}\" is archived too, so time isn't moved on again",
+                    successor
+                ),
+            ),
+            _ => Check::passed(subject),
+        });
+    }
+
+    let mut clients: Vec<_> = config.client.iter().collect();
+    clients.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, client) in clients {
+        let subject = format!("Rates of client \"{}\"", name);
+        let mut starts: Vec<_> = client.rates.iter().map(|r| (&r.project, r.from)).collect();
+        starts.sort();
+        let repeated = starts.windows(2).find(|pair| pair[0] == pair[1]);
+        checks.push(match repeated {
+            Some(pair) => Check::failed(
+                subject,
+                format!(
+                    "two rates start on {}, so which applies is unclear",
+                    pair[0].1
+                ),
+            ),
+            None => Check::passed(subject),
+        });
+    }
+
+    checks
+}
+
+/// The services the config file sets up, and the URL each is reached at
+pub fn endpoints(config: &Config) -> Vec<(&'static str, String)> {
+    let mut endpoints = Vec::new();
+
+    if let Some(url) = config.caldav.as_ref().and_then(|c| c.url.clone()) {
+        endpoints.push(("CalDAV", url));
+    }
+    if config.clockify.is_some() {
+        endpoints.push(("Clockify", "https://api.clockify.me".to_owned()));
+    }
+    if config.google.is_some() {
+        endpoints.push(("Google Calendar", "https://www.googleapis.com".to_owned()));
+    }
+    if config
+        .holidays
+        .as_ref()
+        .is_some_and(|h| h.country.is_some())
+    {
+        endpoints.push(("Nager.Date", "https://date.nager.at".to_owned()));
+    }
+    if let Some(url) = config.jira.as_ref().and_then(|j| j.url.clone()) {
+        endpoints.push(("Jira", url));
+    }
+    if config.toggl.is_some() {
+        endpoints.push(("Toggl", "https://api.track.toggl.com".to_owned()));
+    }
+    if let Some(wakatime) = &config.wakatime {
+        let url = wakatime
+            .api_url
+            .clone()
+            .unwrap_or_else(|| crate::import::wakatime::DEFAULT_API_URL.to_owned());
+        endpoints.push(("WakaTime", url));
+    }
+
+    endpoints
+}
+
+/// Opens a connection to the URL's host, without sending a request that
+/// would need credentials
+pub fn reachable(url: &str) -> Result<(), String> {
+    let (scheme, rest) = url
+        .split_once("://")
+        .ok_or_else(|| "it isn't an http(s) URL".to_owned())?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit('@').next().unwrap_or_default();
+    let address = match (host_port.rsplit_once(':'), scheme) {
+        (Some((_, port)), _) if port.chars().all(|c| c.is_ascii_digit()) => host_port.to_owned(),
+        (_, "http") => format!("{}:80", host_port),
+        _ => format!("{}:443", host_port),
+    };
+
+    let addresses = address
+        .to_socket_addrs()
+        .map_err(|err| format!("unable to look up {}: {}", address, err))?;
+    let mut last_error = format!("{} has no addresses", address);
+    for address in addresses {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(_) => return Ok(()),
+            Err(err) => last_error = format!("unable to connect to {}: {}", address, err),
+        }
+    }
+
+    Err(last_error)
+}
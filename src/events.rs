@@ -0,0 +1,72 @@
+//! Structured progress events for programs that wrap the command line.
+//!
+//! With `--events jsonl` every step of a run is written to stdout as one JSON
+//! object per line, so a GUI can show real progress instead of scraping the
+//! human-readable output. The rendered invoice travels in the final event.
+
+use crate::render::Format;
+use crate::Money;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::Write;
+
+/// A format progress events can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EventFormat {
+    /// One JSON object per line
+    Jsonl,
+}
+
+/// A step of a run, tagged by its `event` field
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// Reading entries from a file or fetching them from a service has begun
+    ImportStarted { from: String },
+    /// The entries have been read, before any are rendered
+    EntriesParsed { count: usize },
+    /// Something about the entries that's worth a look
+    Warning { message: &'a str },
+    /// The invoice was held back, such as when it's below the minimum charge
+    InvoiceDeferred { reason: &'a str },
+    /// The invoice's figures have been worked out
+    InvoiceBuilt { total_time: f64, total: Money },
+    /// The invoice has been rendered, with the output it would have printed
+    ArtifactWritten { format: Format, content: &'a str },
+}
+
+impl Event<'_> {
+    /// Writes the event as a single line of JSON
+    pub fn to_jsonl(&self) -> Result<String> {
+        serde_json::to_string(self).context("Unable to serialize a progress event")
+    }
+}
+
+/// Writes events to stdout when they were asked for, and otherwise drops them
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Events {
+    format: Option<EventFormat>,
+}
+
+impl Events {
+    pub fn new(format: Option<EventFormat>) -> Self {
+        Self { format }
+    }
+
+    /// Whether events are being written, in which case stdout carries nothing else
+    pub fn enabled(&self) -> bool {
+        self.format.is_some()
+    }
+
+    pub fn emit(&self, event: Event) -> Result<()> {
+        match self.format {
+            Some(EventFormat::Jsonl) => {
+                let mut stdout = std::io::stdout().lock();
+                writeln!(stdout, "{}", event.to_jsonl()?)?;
+                stdout.flush().context("Unable to write a progress event")
+            }
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Public holidays, which hours can be charged more for with
+//! `--holiday-multiplier`.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::ops::RangeInclusive;
+
+/// Looks up the public holidays a whole country observes in some years
+/// through the Nager.Date API, by its ISO 3166 code such as "MT"
+///
+/// Holidays only kept in some regions are left out.
+pub fn public_holidays(country: &str, years: RangeInclusive<i32>) -> Result<Vec<NaiveDate>> {
+    #[cfg(feature = "http")]
+    {
+        #[derive(serde::Deserialize)]
+        struct Holiday {
+            date: NaiveDate,
+            global: bool,
+        }
+
+        let mut dates = Vec::new();
+        for year in years {
+            let url = format!("{}/{}/{}", NAGER_DATE_API_URL, year, country.trim());
+            let holidays: Vec<Holiday> = crate::http::get_json(&url, &[], &[])?;
+            dates.extend(holidays.into_iter().filter(|h| h.global).map(|h| h.date));
+        }
+
+        Ok(dates)
+    }
+
+    #[cfg(not(feature = "http"))]
+    {
+        let _ = (country, years);
+        Err(crate::missing_feature("Looking up public holidays", "http"))
+    }
+}
+
+#[cfg(feature = "http")]
+pub(crate) const NAGER_DATE_API_URL: &str = "https://date.nager.at/api/v3/PublicHolidays";
@@ -0,0 +1,127 @@
+//! Small blocking HTTP helpers shared by the API-backed time sources.
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use ureq::http::HeaderMap;
+
+/// The largest file downloaded for `--file`, well above any real timesheet
+const MAX_DOWNLOAD_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Builds an `Authorization` header value for HTTP basic auth
+pub(crate) fn basic_auth(user: &str, password: &str) -> String {
+    let credentials =
+        base64::engine::general_purpose::STANDARD.encode(format!("{user}:{password}"));
+    format!("Basic {credentials}")
+}
+
+/// Performs a GET request and deserializes the JSON response
+pub(crate) fn get_json<T: DeserializeOwned>(
+    url: &str,
+    headers: &[(&str, &str)],
+    query: &[(&str, &str)],
+) -> Result<T> {
+    let mut request = ureq::get(url).header("Accept", "application/json");
+    for (key, value) in query {
+        request = request.query(key, value);
+    }
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+
+    let mut response = request
+        .call()
+        .with_context(|| format!("Request to \"{}\" failed", url))?;
+
+    response
+        .body_mut()
+        .read_json()
+        .with_context(|| format!("Unable to parse the response from \"{}\"", url))
+}
+
+/// Performs a POST request with a JSON body, returning the JSON response and its headers
+pub(crate) fn post_json<B: Serialize, T: DeserializeOwned>(
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &B,
+) -> Result<(T, HeaderMap)> {
+    let mut request = ureq::post(url).header("Accept", "application/json");
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+
+    let mut response = request
+        .send_json(body)
+        .with_context(|| format!("Request to \"{}\" failed", url))?;
+
+    let value = response
+        .body_mut()
+        .read_json()
+        .with_context(|| format!("Unable to parse the response from \"{}\"", url))?;
+
+    Ok((value, response.headers().clone()))
+}
+
+/// Performs a POST request with a form-encoded body and deserializes the JSON response
+pub(crate) fn post_form<T: DeserializeOwned>(url: &str, form: &[(&str, &str)]) -> Result<T> {
+    let mut response = ureq::post(url)
+        .header("Accept", "application/json")
+        .send_form(form.iter().copied())
+        .with_context(|| format!("Request to \"{}\" failed", url))?;
+
+    response
+        .body_mut()
+        .read_json()
+        .with_context(|| format!("Unable to parse the response from \"{}\"", url))
+}
+
+/// Performs a request with any method, such as WebDAV's `REPORT`, returning the response text
+pub(crate) fn request_text(
+    method: &str,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: String,
+) -> Result<String> {
+    let mut request = ureq::http::Request::builder().method(method).uri(url);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let request = request
+        .body(body)
+        .with_context(|| format!("Unable to build a {} request to \"{}\"", method, url))?;
+
+    let agent = ureq::Agent::new_with_defaults();
+    let request = agent
+        .configure_request(request)
+        .allow_non_standard_methods(true)
+        .build();
+
+    let mut response = agent
+        .run(request)
+        .with_context(|| format!("Request to \"{}\" failed", url))?;
+
+    response
+        .body_mut()
+        .read_to_string()
+        .with_context(|| format!("Unable to read the response from \"{}\"", url))
+}
+
+/// Performs a GET request and returns the raw response body
+pub(crate) fn get_bytes(url: &str, headers: &[(&str, &str)]) -> Result<Vec<u8>> {
+    let mut request = ureq::get(url);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+
+    let mut response = request
+        .call()
+        .with_context(|| format!("Request to \"{}\" failed", url))?;
+
+    response
+        .body_mut()
+        .with_config()
+        .limit(MAX_DOWNLOAD_BYTES)
+        .read_to_vec()
+        .with_context(|| format!("Unable to read the response from \"{}\"", url))
+}
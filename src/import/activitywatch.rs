@@ -0,0 +1,260 @@
+//! Window activity from a local ActivityWatch server.
+//!
+//! Events from the window watcher are matched against the rules in a mapping
+//! file to decide which project they belong to. Events no rule matches are
+//! treated as personal and left out, as is any time the AFK watcher saw you away:
+//!
+//! ```toml
+//! [[rule]]
+//! project = "Acme Website"
+//! title = "acme"
+//!
+//! [[rule]]
+//! project = "Internal"
+//! app = "^(slack|thunderbird)$"
+//! ```
+//!
+//! Patterns are case-insensitive regular expressions and the first matching
+//! rule wins. Matched time is totalled per project for each day.
+
+// The response types are only read by `fetch_entries`, and the tests
+#![cfg_attr(not(feature = "http"), allow(dead_code))]
+
+use super::TimeEntry;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Command-line flags for the ActivityWatch source
+#[derive(clap::Args, Debug, Clone, Default)]
+#[command(next_help_heading = "ActivityWatch")]
+pub struct ActivityWatchArgs {
+    /// The ActivityWatch server to read events from
+    #[arg(long, value_name = "URL", default_value = "http://localhost:5600")]
+    pub aw_url: String,
+
+    /// The window watcher bucket to read (defaults to the first aw-watcher-window bucket)
+    #[arg(long, value_name = "BUCKET")]
+    pub aw_bucket: Option<String>,
+
+    /// The TOML file of rules mapping windows to projects
+    #[arg(long, value_name = "FILE")]
+    pub aw_mapping: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawMapping {
+    #[serde(default)]
+    rule: Vec<RawRule>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRule {
+    project: String,
+    app: Option<String>,
+    title: Option<String>,
+}
+
+/// A rule billing windows whose app and title match to a project
+#[derive(Debug)]
+pub struct Rule {
+    pub project: String,
+    pub app: Option<Regex>,
+    pub title: Option<Regex>,
+}
+
+#[derive(Debug)]
+pub struct Mapping {
+    pub rules: Vec<Rule>,
+}
+
+impl Mapping {
+    pub fn parse(contents: &str) -> Result<Self> {
+        let raw: RawMapping = toml::from_str(contents)?;
+
+        let pattern = |pattern: Option<String>| {
+            pattern
+                .map(|p| {
+                    RegexBuilder::new(&p)
+                        .case_insensitive(true)
+                        .build()
+                        .with_context(|| format!("Invalid pattern \"{}\"", p))
+                })
+                .transpose()
+        };
+
+        let rules = raw
+            .rule
+            .into_iter()
+            .map(|rule| {
+                if rule.app.is_none() && rule.title.is_none() {
+                    anyhow::bail!("The rule for \"{}\" needs an app or title", rule.project);
+                }
+
+                Ok(Rule {
+                    app: pattern(rule.app)?,
+                    title: pattern(rule.title)?,
+                    project: rule.project,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { rules })
+    }
+
+    /// The project of the first rule matching the window
+    pub fn project_for(&self, app: &str, title: &str) -> Option<&str> {
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.app.as_ref().is_none_or(|r| r.is_match(app))
+                    && rule.title.as_ref().is_none_or(|r| r.is_match(title))
+            })
+            .map(|rule| rule.project.as_str())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Event {
+    pub timestamp: DateTime<FixedOffset>,
+    /// Seconds
+    pub duration: f64,
+    pub data: EventData,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct EventData {
+    pub app: String,
+    pub title: String,
+    pub status: String,
+}
+
+impl Event {
+    fn end(&self) -> DateTime<FixedOffset> {
+        self.timestamp + Duration::milliseconds((self.duration * 1000.0).round() as i64)
+    }
+}
+
+/// The time spans the AFK watcher saw you at the computer, sorted by start
+pub(crate) fn active_spans(
+    afk_events: &[Event],
+) -> Vec<(DateTime<FixedOffset>, DateTime<FixedOffset>)> {
+    let mut spans: Vec<_> = afk_events
+        .iter()
+        .filter(|e| e.data.status == "not-afk")
+        .map(|e| (e.timestamp, e.end()))
+        .collect();
+    spans.sort();
+
+    spans
+}
+
+/// Totals the mapped window time, less any time away, per project per day
+pub(crate) fn mapped_entries(
+    window_events: &[Event],
+    active: Option<&[(DateTime<FixedOffset>, DateTime<FixedOffset>)]>,
+    mapping: &Mapping,
+) -> Vec<TimeEntry> {
+    let mut totals: BTreeMap<(NaiveDate, &str), Duration> = BTreeMap::new();
+
+    for event in window_events {
+        let Some(project) = mapping.project_for(&event.data.app, &event.data.title) else {
+            continue;
+        };
+
+        let (start, end) = (event.timestamp, event.end());
+        let duration = match active {
+            Some(active) => active
+                .iter()
+                .filter(|(s, e)| *s < end && *e > start)
+                .map(|(s, e)| (*e).min(end) - (*s).max(start))
+                .fold(Duration::zero(), |total, overlap| total + overlap),
+            None => end - start,
+        };
+        if duration <= Duration::zero() {
+            continue;
+        }
+
+        let date = start.with_timezone(&Local).date_naive();
+        *totals.entry((date, project)).or_insert_with(Duration::zero) += duration;
+    }
+
+    totals
+        .into_iter()
+        .map(|((date, project), duration)| {
+            let mut entry = TimeEntry::new(project, duration);
+            entry.date = Some(date);
+            entry
+        })
+        .collect()
+}
+
+#[cfg(feature = "http")]
+pub fn fetch_entries(
+    args: &ActivityWatchArgs,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<TimeEntry>> {
+    use crate::http;
+    use chrono::{NaiveTime, TimeZone};
+    use std::collections::HashMap;
+
+    let mapping_file = args
+        .aw_mapping
+        .as_ref()
+        .context("--aw-mapping is required for ActivityWatch")?;
+    let contents = std::fs::read_to_string(mapping_file)
+        .with_context(|| format!("Unable to read from given file \"{:?}\"", mapping_file))?;
+    let mapping = Mapping::parse(&contents)
+        .with_context(|| format!("Unable to parse mapping file \"{:?}\"", mapping_file))?;
+
+    let api = format!("{}/api/0", args.aw_url.trim_end_matches('/'));
+    let buckets: HashMap<String, serde_json::Value> =
+        http::get_json(&format!("{}/buckets/", api), &[], &[])
+            .context("Unable to list ActivityWatch buckets")?;
+
+    let first_bucket = |prefix: &str| {
+        let mut ids: Vec<&String> = buckets.keys().filter(|id| id.starts_with(prefix)).collect();
+        ids.sort();
+        ids.first().map(|id| id.to_string())
+    };
+    let window_bucket = args
+        .aw_bucket
+        .clone()
+        .or_else(|| first_bucket("aw-watcher-window"))
+        .context("No aw-watcher-window bucket found, pass --aw-bucket")?;
+    let afk_bucket = first_bucket("aw-watcher-afk");
+
+    let local_midnight = |date: NaiveDate| {
+        Local
+            .from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|t| t.to_rfc3339())
+            .with_context(|| format!("Local midnight on {} doesn't exist", date))
+    };
+    let start = local_midnight(from)?;
+    let end = local_midnight(to.succ_opt().unwrap_or(to))?;
+
+    let events = |bucket: &str| -> Result<Vec<Event>> {
+        http::get_json(
+            &format!("{}/buckets/{}/events", api, bucket),
+            &[],
+            &[("start", &start), ("end", &end), ("limit", "-1")],
+        )
+        .with_context(|| format!("Unable to fetch events from bucket \"{}\"", bucket))
+    };
+
+    let window_events = events(&window_bucket)?;
+    let active = match &afk_bucket {
+        Some(bucket) => Some(active_spans(&events(bucket)?)),
+        None => None,
+    };
+
+    Ok(mapped_entries(&window_events, active.as_deref(), &mapping))
+}
@@ -0,0 +1,178 @@
+//! Events fetched from a CalDAV calendar, such as on Nextcloud or Fastmail.
+//!
+//! A `calendar-query` REPORT asks the server for the events overlapping the
+//! date range, and each returned calendar object is read the same way as an
+//! `.ics` file, so `--ics-project` and `--ics-prefix` apply here too.
+
+// The response parsing is only used by `fetch_entries`, and the tests
+#![cfg_attr(not(feature = "http"), allow(dead_code))]
+
+use super::ics::IcsArgs;
+use super::TimeEntry;
+use crate::config::CaldavConfig;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use regex::Regex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaldavOptions {
+    /// The calendar collection, e.g. https://cloud.example.com/remote.php/dav/calendars/me/work/
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+/// Command-line flags for the CalDAV source
+#[derive(clap::Args, Debug, Clone, Default)]
+#[command(next_help_heading = "CalDAV")]
+pub struct CaldavArgs {
+    /// The URL of the calendar collection to bill events from
+    #[arg(long, value_name = "URL")]
+    pub caldav_url: Option<String>,
+
+    /// The username used to authenticate with the CalDAV server
+    #[arg(long, value_name = "USERNAME", env = "CALDAV_USERNAME")]
+    pub caldav_username: Option<String>,
+
+    /// The password, or app password, for the username
+    #[arg(
+        long,
+        value_name = "PASSWORD",
+        env = "CALDAV_PASSWORD",
+        hide_env_values = true
+    )]
+    pub caldav_password: Option<String>,
+}
+
+impl CaldavArgs {
+    /// Checks the required connection details were given, falling back to the config file
+    pub fn options(
+        &self,
+        config: Option<&CaldavConfig>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<CaldavOptions> {
+        let config = config.unwrap_or(&CaldavConfig::EMPTY);
+
+        Ok(CaldavOptions {
+            url: self
+                .caldav_url
+                .clone()
+                .or_else(|| config.url.clone())
+                .context("--caldav-url is required for CalDAV")?,
+            username: self
+                .caldav_username
+                .clone()
+                .or_else(|| config.username.clone())
+                .context("--caldav-username (or CALDAV_USERNAME) is required for CalDAV")?,
+            password: self
+                .caldav_password
+                .clone()
+                .or_else(|| config.password.clone())
+                .context("--caldav-password (or CALDAV_PASSWORD) is required for CalDAV")?,
+            from,
+            to,
+        })
+    }
+}
+
+/// Pulls the iCalendar text out of each `calendar-data` element in a multistatus response
+pub(crate) fn calendar_objects(multistatus: &str) -> Vec<String> {
+    let element =
+        Regex::new(r"(?s)<(?:[\w.-]+:)?calendar-data\b[^>]*>(.*?)</(?:[\w.-]+:)?calendar-data\s*>")
+            .unwrap();
+
+    element
+        .captures_iter(multistatus)
+        .map(|c| {
+            let data = c[1].trim();
+            match data
+                .strip_prefix("<![CDATA[")
+                .and_then(|d| d.strip_suffix("]]>"))
+            {
+                Some(data) => data.to_owned(),
+                None => unescape_xml(data),
+            }
+        })
+        .collect()
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#13;", "\r")
+        .replace("&#xD;", "\r")
+        .replace("&#10;", "\n")
+        .replace("&amp;", "&")
+}
+
+/// Reads the events in each calendar object like an `.ics` file
+pub(crate) fn object_entries(
+    objects: &[String],
+    args: &IcsArgs,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<TimeEntry>> {
+    let mut entries = Vec::new();
+    for object in objects {
+        entries.extend(super::ics::parse_entries(object, args, from, to)?);
+    }
+
+    Ok(entries)
+}
+
+#[cfg(feature = "http")]
+pub fn fetch_entries(options: &CaldavOptions, args: &IcsArgs) -> Result<Vec<TimeEntry>> {
+    use crate::http;
+    use chrono::{Duration, Local, NaiveTime, TimeZone, Utc};
+
+    let utc_midnight = |date: NaiveDate| {
+        Local
+            .from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|t| t.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string())
+            .context("Local midnight doesn't exist on the range's first or last day")
+    };
+
+    let query = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><c:calendar-data/></d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR">
+      <c:comp-filter name="VEVENT">
+        <c:time-range start="{}" end="{}"/>
+      </c:comp-filter>
+    </c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#,
+        utc_midnight(options.from)?,
+        utc_midnight(options.to + Duration::days(1))?
+    );
+
+    let multistatus = http::request_text(
+        "REPORT",
+        &options.url,
+        &[
+            (
+                "Authorization",
+                &http::basic_auth(&options.username, &options.password),
+            ),
+            ("Depth", "1"),
+            ("Content-Type", "application/xml; charset=utf-8"),
+        ],
+        query,
+    )
+    .context("Unable to query the CalDAV calendar")?;
+
+    object_entries(
+        &calendar_objects(&multistatus),
+        args,
+        options.from,
+        options.to,
+    )
+}
@@ -0,0 +1,126 @@
+//! Time entries fetched from the Clockify API.
+//!
+//! Entries are read for the authenticated user in the configured workspace,
+//! hydrated so each one carries its project's name.
+
+// The response types are only read by `fetch_entries`, and the tests
+#![cfg_attr(not(feature = "http"), allow(dead_code))]
+
+use super::TimeEntry;
+#[cfg(feature = "http")]
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Deserialize;
+
+/// The project name Clockify itself shows for entries without a project
+pub const NO_PROJECT: &str = "(Without project)";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClockifyOptions {
+    pub api_key: String,
+    pub workspace_id: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ClockifyEntry {
+    pub time_interval: TimeInterval,
+    pub project: Option<Project>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct TimeInterval {
+    pub start: DateTime<Utc>,
+    /// Missing while the timer is still running
+    pub end: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Project {
+    pub name: String,
+}
+
+/// Converts hydrated time entries into ours, skipping running timers
+pub(crate) fn time_entries(entries: &[ClockifyEntry]) -> Vec<TimeEntry> {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let end = entry.time_interval.end?;
+            let project = entry
+                .project
+                .as_ref()
+                .map(|p| p.name.as_str())
+                .unwrap_or(NO_PROJECT);
+
+            Some(
+                TimeEntry::new(project, end - entry.time_interval.start)
+                    .started_at_instant(&entry.time_interval.start),
+            )
+        })
+        .collect()
+}
+
+#[cfg(feature = "http")]
+pub fn fetch_entries(options: &ClockifyOptions) -> Result<Vec<TimeEntry>> {
+    use crate::http;
+    use anyhow::Context;
+    use chrono::{Local, NaiveTime, TimeZone};
+
+    const API: &str = "https://api.clockify.me/api/v1";
+    const PAGE_SIZE: usize = 200;
+
+    #[derive(Deserialize)]
+    struct User {
+        id: String,
+    }
+
+    let headers = [("X-Api-Key", options.api_key.as_str())];
+
+    let user: User = http::get_json(&format!("{}/user", API), &headers, &[])
+        .context("Unable to look up the authenticated Clockify user")?;
+
+    // The range covers whole local days, which Clockify wants as UTC instants
+    let local_midnight = |date: NaiveDate| {
+        Local
+            .from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|t| t.with_timezone(&Utc))
+            .with_context(|| format!("Local midnight on {} doesn't exist", date))
+    };
+    let start = local_midnight(options.from)?
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string();
+    let end = local_midnight(options.to.succ_opt().unwrap_or(options.to))?
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string();
+
+    let mut entries = Vec::new();
+    for page in 1.. {
+        let page_number = page.to_string();
+        let page_size = PAGE_SIZE.to_string();
+        let page_entries: Vec<ClockifyEntry> = http::get_json(
+            &format!(
+                "{}/workspaces/{}/user/{}/time-entries",
+                API, options.workspace_id, user.id
+            ),
+            &headers,
+            &[
+                ("start", &start),
+                ("end", &end),
+                ("hydrated", "true"),
+                ("page", &page_number),
+                ("page-size", &page_size),
+            ],
+        )
+        .context("Unable to fetch Clockify time entries")?;
+
+        entries.extend(time_entries(&page_entries));
+        if page_entries.len() < PAGE_SIZE {
+            break;
+        }
+    }
+
+    Ok(entries)
+}
@@ -0,0 +1,104 @@
+//! Fixed-width timesheet dumps, where every field sits at the same byte
+//! offsets on each line:
+//!
+//! ```text
+//! PROJECT             DATE      HOURS
+//! Acme Website        20240603  01:30:00
+//! ```
+//!
+//! The project and duration are cut out by the byte ranges given with
+//! `--fixed-width`, and trailing padding is trimmed. A first line whose
+//! duration doesn't parse is taken to be a header.
+
+use super::TimeEntry;
+use crate::DurationFormat;
+use anyhow::{Context, Result};
+use std::ops::Range;
+
+/// Where the project and duration are on each line, as zero-based byte ranges
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedWidth {
+    pub project: Range<usize>,
+    pub duration: Range<usize>,
+}
+
+impl std::str::FromStr for FixedWidth {
+    type Err = anyhow::Error;
+
+    /// Parses `START-END,START-END` for the project then the duration, ends exclusive
+    fn from_str(str: &str) -> Result<Self> {
+        let (project, duration) = str
+            .split_once(',')
+            .with_context(|| format!("\"{}\" should look like 0-20,40-48", str))?;
+
+        Ok(Self {
+            project: parse_range(project)?,
+            duration: parse_range(duration)?,
+        })
+    }
+}
+
+fn parse_range(str: &str) -> Result<Range<usize>> {
+    let (start, end) = str
+        .trim()
+        .split_once('-')
+        .with_context(|| format!("Byte range \"{}\" should look like 0-20", str))?;
+    let start: usize = start
+        .trim()
+        .parse()
+        .with_context(|| format!("Unable to parse the start of \"{}\"", str))?;
+    let end: usize = end
+        .trim()
+        .parse()
+        .with_context(|| format!("Unable to parse the end of \"{}\"", str))?;
+    if end <= start {
+        anyhow::bail!("Byte range \"{}\" ends before it starts", str);
+    }
+
+    Ok(start..end)
+}
+
+/// Parses every non-blank line into an entry
+pub fn parse_entries(
+    contents: &str,
+    layout: &FixedWidth,
+    format: DurationFormat,
+) -> Result<Vec<TimeEntry>> {
+    let mut entries = Vec::new();
+    let mut first = true;
+
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let project = field(line, &layout.project)
+            .with_context(|| format!("Unable to read the project on line {}", index + 1))?;
+        let duration = field(line, &layout.duration)
+            .with_context(|| format!("Unable to read the duration on line {}", index + 1))?;
+        let duration = match (format.parse(duration), std::mem::take(&mut first)) {
+            (Ok(duration), _) => duration,
+            (Err(_), true) => continue,
+            (Err(err), false) => {
+                return Err(err.context(format!(
+                    "Unable to parse the duration on line {}",
+                    index + 1
+                )))
+            }
+        };
+
+        entries.push(TimeEntry::new(project, duration));
+    }
+
+    Ok(entries)
+}
+
+/// Cuts a field out of a line, which may be shorter than the field if its padding was trimmed
+fn field<'a>(line: &'a str, range: &Range<usize>) -> Result<&'a str> {
+    let end = range.end.min(line.len());
+    let field = line
+        .get(range.start.min(end)..end)
+        .context("The range splits a character in two")?;
+
+    Ok(field.trim())
+}
@@ -0,0 +1,220 @@
+//! Hours estimated from the commit history of git repositories.
+//!
+//! Commits close enough together are treated as one working session, the
+//! gaps between them counted as time worked. Every session also earns a fixed
+//! allowance for the work done before its first commit. This is only ever an
+//! estimate, but it's a reasonable one for retroactively billing small jobs.
+//!
+//! Commits are billed under their repository's directory name, or under the
+//! value of a `Project:` trailer in the commit message when grouping by trailer.
+
+use super::TimeEntry;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate};
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const FIELD_SEPARATOR: char = '\x1f';
+const RECORD_SEPARATOR: char = '\x1e';
+
+/// What commits are billed under on the invoice
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum GitGrouping {
+    /// The name of the repository's directory
+    #[default]
+    Repo,
+    /// A `Project:` trailer in the commit message, falling back to the repository
+    Trailer,
+}
+
+/// Command-line flags for the git source
+#[derive(clap::Args, Debug, Clone, Default)]
+#[command(next_help_heading = "Git")]
+pub struct GitArgs {
+    /// A repository to estimate hours from, can be given more than once (defaults to .)
+    #[arg(long, value_name = "PATH")]
+    pub git_repo: Vec<PathBuf>,
+
+    /// Only count commits by authors matching this pattern (defaults to your user.email)
+    #[arg(long, value_name = "PATTERN")]
+    pub git_author: Option<String>,
+
+    /// The longest gap between commits, in minutes, that still counts as one session
+    #[arg(long, value_name = "MINUTES", default_value_t = 120)]
+    pub git_session_gap: i64,
+
+    /// Minutes credited for the work before the first commit of each session
+    #[arg(long, value_name = "MINUTES", default_value_t = 30)]
+    pub git_first_commit: i64,
+
+    /// Whether to bill commits per repository or per `Project:` trailer
+    #[arg(long, value_enum, default_value_t)]
+    pub git_group_by: GitGrouping,
+}
+
+/// A commit's author time and the project it's billed under
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Commit {
+    pub project: String,
+    pub time: DateTime<FixedOffset>,
+}
+
+/// Runs `git log` over each repository and estimates sessions from the commits
+pub fn estimate_entries(args: &GitArgs, from: NaiveDate, to: NaiveDate) -> Result<Vec<TimeEntry>> {
+    let default_repo = [PathBuf::from(".")];
+    let repos = if args.git_repo.is_empty() {
+        &default_repo[..]
+    } else {
+        &args.git_repo[..]
+    };
+
+    let mut commits = Vec::new();
+    for repo in repos {
+        let author = match &args.git_author {
+            Some(author) => author.clone(),
+            None => git(repo, &["config", "user.email"])
+                .context("Unable to find your git user.email, pass --git-author instead")?
+                .trim()
+                .to_owned(),
+        };
+
+        let format = format!(
+            "--format=%aI{}%(trailers:key=Project,valueonly,separator=%x2C){}",
+            FIELD_SEPARATOR, RECORD_SEPARATOR
+        );
+        let since = format!("--since={}T00:00:00", from);
+        let until = format!("--until={}T23:59:59", to);
+        let author = format!("--author={}", author);
+        let log = git(
+            repo,
+            &[
+                "log",
+                "--all",
+                "--no-merges",
+                &since,
+                &until,
+                &author,
+                &format,
+            ],
+        )?;
+
+        commits.extend(
+            parse_log(&log, &repo_name(repo)?, args.git_group_by)
+                .with_context(|| format!("Unable to parse the git log of \"{:?}\"", repo))?,
+        );
+    }
+
+    Ok(sessions(
+        &commits,
+        Duration::minutes(args.git_session_gap),
+        Duration::minutes(args.git_first_commit),
+    ))
+}
+
+fn git(repo: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .context("Unable to run git")?;
+
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    String::from_utf8(output.stdout).context("git printed invalid UTF-8")
+}
+
+fn repo_name(repo: &Path) -> Result<String> {
+    let path = repo
+        .canonicalize()
+        .with_context(|| format!("Unable to find repository \"{:?}\"", repo))?;
+
+    Ok(path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string_lossy().into_owned()))
+}
+
+/// Parses the records printed by `git log` with the format used above
+pub(crate) fn parse_log(log: &str, repo: &str, grouping: GitGrouping) -> Result<Vec<Commit>> {
+    log.split(RECORD_SEPARATOR)
+        .map(str::trim)
+        .filter(|record| !record.is_empty())
+        .map(|record| {
+            let (time, trailer) = record.split_once(FIELD_SEPARATOR).unwrap_or((record, ""));
+            let time = DateTime::parse_from_rfc3339(time.trim())
+                .with_context(|| format!("Unable to parse commit time \"{}\"", time))?;
+
+            // Only the first trailer counts if a commit has several
+            let trailer = trailer.split(',').next().unwrap_or_default().trim();
+            let project = match grouping {
+                GitGrouping::Trailer if !trailer.is_empty() => trailer,
+                _ => repo,
+            };
+
+            Ok(Commit {
+                project: project.to_owned(),
+                time,
+            })
+        })
+        .collect()
+}
+
+/// Groups each project's commits into sessions, one time entry per session
+pub(crate) fn sessions(
+    commits: &[Commit],
+    max_gap: Duration,
+    first_commit: Duration,
+) -> Vec<TimeEntry> {
+    let mut by_project: BTreeMap<&str, Vec<DateTime<FixedOffset>>> = BTreeMap::new();
+    for commit in commits {
+        by_project
+            .entry(&commit.project)
+            .or_default()
+            .push(commit.time);
+    }
+
+    let mut entries = Vec::new();
+    for (project, mut times) in by_project {
+        times.sort();
+
+        let mut session: Option<(DateTime<FixedOffset>, DateTime<FixedOffset>)> = None;
+        for time in times {
+            session = match session {
+                Some((start, last)) if time - last <= max_gap => Some((start, time)),
+                Some((start, last)) => {
+                    entries.push(session_entry(project, start, last, first_commit));
+                    Some((time, time))
+                }
+                None => Some((time, time)),
+            };
+        }
+
+        if let Some((start, last)) = session {
+            entries.push(session_entry(project, start, last, first_commit));
+        }
+    }
+
+    entries
+}
+
+fn session_entry(
+    project: &str,
+    first: DateTime<FixedOffset>,
+    last: DateTime<FixedOffset>,
+    first_commit: Duration,
+) -> TimeEntry {
+    let start = first - first_commit;
+
+    TimeEntry::new(project, last - start)
+        .started_at_instant(&start)
+        .as_estimate()
+}
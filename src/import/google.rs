@@ -0,0 +1,260 @@
+//! Events fetched from a Google Calendar with the Calendar v3 API.
+//!
+//! Recurring events are expanded by Google (`singleEvents=true`), so every
+//! timed event in the range becomes one entry under its title. All-day,
+//! cancelled and declined events aren't billed. As with `.ics` files, a
+//! prefix limits billing to events titled `<prefix> <project>`.
+//!
+//! The API is authorised either with an access token passed in directly
+//! (such as from `gcloud auth print-access-token`) or with an OAuth client
+//! and refresh token kept in the config file, which is exchanged for a fresh
+//! access token on every run.
+
+// The response types are only read by `fetch_entries`, and the tests
+#![cfg_attr(not(feature = "http"), allow(dead_code))]
+
+use super::TimeEntry;
+use crate::config::GoogleConfig;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate};
+use serde::Deserialize;
+
+#[cfg(feature = "http")]
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+#[cfg(feature = "http")]
+const API_URL: &str = "https://www.googleapis.com/calendar/v3";
+
+/// How requests to the Calendar API are authorised
+#[derive(Debug, Clone, PartialEq)]
+pub enum GoogleAuth {
+    AccessToken(String),
+    RefreshToken {
+        client_id: String,
+        client_secret: String,
+        refresh_token: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoogleOptions {
+    pub auth: GoogleAuth,
+    pub calendar_id: String,
+    pub prefix: Option<String>,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+/// Command-line flags for the Google Calendar source
+#[derive(clap::Args, Debug, Clone, Default)]
+#[command(next_help_heading = "Google Calendar")]
+pub struct GoogleArgs {
+    /// The calendar to bill events from (defaults to your primary calendar)
+    #[arg(long, value_name = "CALENDAR_ID")]
+    pub google_calendar: Option<String>,
+
+    /// An OAuth access token to use instead of the refresh token in the config file
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        env = "GOOGLE_ACCESS_TOKEN",
+        hide_env_values = true
+    )]
+    pub google_token: Option<String>,
+
+    /// Only bill events whose title starts with this, under the rest of the title
+    #[arg(long, value_name = "PREFIX")]
+    pub google_prefix: Option<String>,
+}
+
+impl GoogleArgs {
+    /// Works out how to authorise from the flags, falling back to the config file
+    pub fn options(
+        &self,
+        config: Option<&GoogleConfig>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<GoogleOptions> {
+        let config = config.unwrap_or(&GoogleConfig::EMPTY);
+
+        let auth = match &self.google_token {
+            Some(token) => GoogleAuth::AccessToken(token.clone()),
+            None => match (&config.client_id, &config.client_secret, &config.refresh_token) {
+                (Some(client_id), Some(client_secret), Some(refresh_token)) => {
+                    GoogleAuth::RefreshToken {
+                        client_id: client_id.clone(),
+                        client_secret: client_secret.clone(),
+                        refresh_token: refresh_token.clone(),
+                    }
+                }
+                _ => anyhow::bail!(
+                    "--google-token (or GOOGLE_ACCESS_TOKEN), or a [google] section with a client_id, client_secret and refresh_token, is required for Google Calendar"
+                ),
+            },
+        };
+
+        Ok(GoogleOptions {
+            auth,
+            calendar_id: self
+                .google_calendar
+                .clone()
+                .or_else(|| config.calendar.clone())
+                .unwrap_or_else(|| "primary".to_owned()),
+            prefix: self.google_prefix.clone(),
+            from,
+            to,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EventsPage {
+    #[serde(default)]
+    pub items: Vec<Event>,
+    pub next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Event {
+    pub status: Option<String>,
+    pub summary: Option<String>,
+    pub start: EventTime,
+    pub end: EventTime,
+    #[serde(default)]
+    pub attendees: Vec<Attendee>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EventTime {
+    /// Only set for timed events, all-day events have a `date` instead
+    pub date_time: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Attendee {
+    #[serde(rename = "self", default)]
+    pub is_self: bool,
+    pub response_status: Option<String>,
+}
+
+impl Event {
+    fn declined(&self) -> bool {
+        self.attendees
+            .iter()
+            .any(|a| a.is_self && a.response_status.as_deref() == Some("declined"))
+    }
+}
+
+/// Turns the timed events the user is going to into entries
+pub(crate) fn event_entries(events: &[Event], prefix: Option<&str>) -> Result<Vec<TimeEntry>> {
+    let mut entries = Vec::new();
+
+    for event in events {
+        if event.status.as_deref() == Some("cancelled") || event.declined() {
+            continue;
+        }
+        let (Some(start), Some(end)) = (&event.start.date_time, &event.end.date_time) else {
+            continue;
+        };
+
+        let summary = event.summary.as_deref().unwrap_or_default().trim();
+        let project = match prefix {
+            Some(prefix) => match summary.strip_prefix(prefix) {
+                Some(rest) => rest.trim(),
+                None => continue,
+            },
+            None => summary,
+        };
+        if project.is_empty() {
+            continue;
+        }
+
+        let start = DateTime::parse_from_rfc3339(start)
+            .with_context(|| format!("Unable to parse event start \"{}\"", start))?;
+        let end = DateTime::parse_from_rfc3339(end)
+            .with_context(|| format!("Unable to parse event end \"{}\"", end))?;
+
+        entries.push(TimeEntry::new(project, end - start).started_at_instant(&start));
+    }
+
+    Ok(entries)
+}
+
+#[cfg(feature = "http")]
+pub fn fetch_entries(options: &GoogleOptions) -> Result<Vec<TimeEntry>> {
+    use crate::http;
+    use chrono::{Duration, Local, NaiveTime, TimeZone};
+
+    #[derive(Deserialize)]
+    struct Token {
+        access_token: String,
+    }
+
+    let access_token = match &options.auth {
+        GoogleAuth::AccessToken(token) => token.clone(),
+        GoogleAuth::RefreshToken {
+            client_id,
+            client_secret,
+            refresh_token,
+        } => {
+            let token: Token = http::post_form(
+                TOKEN_URL,
+                &[
+                    ("client_id", client_id),
+                    ("client_secret", client_secret),
+                    ("refresh_token", refresh_token),
+                    ("grant_type", "refresh_token"),
+                ],
+            )
+            .context("Unable to refresh the Google access token")?;
+            token.access_token
+        }
+    };
+    let authorization = format!("Bearer {}", access_token);
+
+    let local_midnight = |date: NaiveDate| {
+        Local
+            .from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|t| t.to_rfc3339())
+            .context("Local midnight doesn't exist on the range's first or last day")
+    };
+    let time_min = local_midnight(options.from)?;
+    let time_max = local_midnight(options.to + Duration::days(1))?;
+
+    // Calendar IDs are usually email addresses, which need their `@` escaped in the path
+    let url = format!(
+        "{}/calendars/{}/events",
+        API_URL,
+        options.calendar_id.replace('@', "%40").replace('#', "%23")
+    );
+
+    let mut events = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut query = vec![
+            ("timeMin", time_min.as_str()),
+            ("timeMax", time_max.as_str()),
+            ("singleEvents", "true"),
+            ("maxResults", "2500"),
+        ];
+        if let Some(token) = &page_token {
+            query.push(("pageToken", token));
+        }
+
+        let page: EventsPage = http::get_json(&url, &[("Authorization", &authorization)], &query)
+            .with_context(|| {
+            format!("Unable to fetch events from \"{}\"", options.calendar_id)
+        })?;
+        events.extend(page.items);
+
+        match page.next_page_token {
+            Some(token) => page_token = Some(token),
+            None => break,
+        }
+    }
+
+    event_entries(&events, options.prefix.as_deref())
+}
@@ -0,0 +1,531 @@
+//! Events from iCalendar (`.ics`) files.
+//!
+//! Every timed event in the date range becomes an entry lasting from its
+//! `DTSTART` to its `DTEND` (or for its `DURATION`). All-day and cancelled
+//! events are left out. The project is the event's `SUMMARY` by default, or
+//! its first `CATEGORIES` value. With a prefix, only events whose summary
+//! starts with it are billed, under the rest of the summary:
+//!
+//! ```text
+//! SUMMARY:Acme: design review    --ics-prefix "Acme:" bills "design review"
+//! ```
+//!
+//! Recurring events are expanded within the range for `DAILY`, `WEEKLY`
+//! (optionally by `BYDAY`), `MONTHLY` and `YEARLY` rules with `INTERVAL`,
+//! `COUNT` and `UNTIL`, honouring `EXDATE` and moved instances that carry a
+//! `RECURRENCE-ID`. Other rule parts are refused rather than guessed at.
+
+use super::TimeEntry;
+use anyhow::{bail, Context, Result};
+use chrono::{
+    DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Utc, Weekday,
+};
+use chrono_tz::Tz;
+use clap::ValueEnum;
+use std::collections::HashSet;
+
+/// Stops runaway rules (such as a daily rule from decades back) expanding forever
+const MAX_OCCURRENCES: usize = 100_000;
+
+/// Which part of an event names its project
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum IcsProject {
+    /// The event's title
+    #[default]
+    Summary,
+    /// The event's first category
+    Category,
+}
+
+/// Command-line flags for iCalendar files
+#[derive(clap::Args, Debug, Clone, Default)]
+#[command(next_help_heading = "iCalendar")]
+pub struct IcsArgs {
+    /// Which part of each event names the project it's billed under
+    #[arg(long, value_enum, default_value_t)]
+    pub ics_project: IcsProject,
+
+    /// Only bill events whose summary starts with this, under the rest of the summary
+    #[arg(long, value_name = "PREFIX")]
+    pub ics_prefix: Option<String>,
+}
+
+/// A property's `KEY=value` parameters, such as `TZID`
+type Params = Vec<(String, String)>;
+
+/// The time zone a calendar time is written in
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Zone {
+    Utc,
+    Named(Tz),
+    /// No zone given, so the time is the same wall-clock time everywhere
+    Floating,
+}
+
+impl Zone {
+    fn instant(&self, time: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Zone::Utc => Some(Utc.from_utc_datetime(&time)),
+            Zone::Named(tz) => tz
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|t| t.with_timezone(&Utc)),
+            Zone::Floating => Local
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|t| t.with_timezone(&Utc)),
+        }
+    }
+
+    fn wall_clock(&self, instant: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Zone::Utc => instant.naive_utc(),
+            Zone::Named(tz) => instant.with_timezone(tz).naive_local(),
+            Zone::Floating => instant.with_timezone(&Local).naive_local(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum CalendarTime {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime, Zone),
+}
+
+#[derive(Debug, Default)]
+struct Event {
+    uid: Option<String>,
+    summary: Option<String>,
+    categories: Vec<String>,
+    start: Option<CalendarTime>,
+    end: Option<CalendarTime>,
+    duration: Option<Duration>,
+    rrule: Option<String>,
+    exdates: Vec<CalendarTime>,
+    recurrence_id: Option<CalendarTime>,
+    cancelled: bool,
+}
+
+/// Parses the timed events in a calendar that start between `from` and `to`
+pub fn parse_entries(
+    contents: &str,
+    args: &IcsArgs,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<TimeEntry>> {
+    let events = parse_events(contents)?;
+
+    // Instances that were moved or cancelled individually replace the
+    // occurrence their RECURRENCE-ID points at
+    let overridden: HashSet<(String, DateTime<Utc>)> = events
+        .iter()
+        .filter_map(|e| {
+            let uid = e.uid.clone()?;
+            let instant = instant(e.recurrence_id.as_ref()?)?;
+            Some((uid, instant))
+        })
+        .collect();
+
+    let mut entries = Vec::new();
+    for event in &events {
+        if event.cancelled {
+            continue;
+        }
+        let Some(project) = project(event, args) else {
+            continue;
+        };
+        let Some(CalendarTime::DateTime(start, zone)) = &event.start else {
+            // All-day events and to-dos without a start aren't billable time
+            continue;
+        };
+
+        let start_instant = zone
+            .instant(*start)
+            .with_context(|| format!("Event start {} doesn't exist in its time zone", start))?;
+        let duration = match (&event.end, event.duration) {
+            (Some(end), _) => {
+                instant(end).with_context(|| format!("Invalid end of event \"{}\"", project))?
+                    - start_instant
+            }
+            (None, Some(duration)) => duration,
+            (None, None) => Duration::zero(),
+        };
+        if duration <= Duration::zero() {
+            continue;
+        }
+
+        let occurrences = match (&event.rrule, &event.recurrence_id) {
+            (Some(rule), None) => expand_rule(rule, *start, *zone, to)
+                .with_context(|| format!("Unable to expand the recurrence of \"{}\"", project))?,
+            _ => vec![start_instant],
+        };
+        let excluded: HashSet<DateTime<Utc>> = event.exdates.iter().filter_map(instant).collect();
+
+        for occurrence in occurrences {
+            let is_overridden = event.recurrence_id.is_none()
+                && event
+                    .uid
+                    .as_ref()
+                    .is_some_and(|uid| overridden.contains(&(uid.clone(), occurrence)));
+            if excluded.contains(&occurrence) || is_overridden {
+                continue;
+            }
+
+            let local_date = occurrence.with_timezone(&Local).date_naive();
+            if local_date < from || local_date > to {
+                continue;
+            }
+
+            entries.push(TimeEntry::new(&project, duration).started_at_instant(&occurrence));
+        }
+    }
+
+    Ok(entries)
+}
+
+fn instant(time: &CalendarTime) -> Option<DateTime<Utc>> {
+    match time {
+        CalendarTime::Date(date) => Zone::Floating.instant(date.and_time(NaiveTime::MIN)),
+        CalendarTime::DateTime(time, zone) => zone.instant(*time),
+    }
+}
+
+fn project(event: &Event, args: &IcsArgs) -> Option<String> {
+    let summary = event.summary.as_deref().unwrap_or_default().trim();
+
+    let summary = match &args.ics_prefix {
+        Some(prefix) => summary.strip_prefix(prefix.as_str())?.trim(),
+        None => summary,
+    };
+
+    let project = match args.ics_project {
+        IcsProject::Summary => summary,
+        IcsProject::Category => event.categories.first()?.as_str(),
+    };
+
+    (!project.is_empty()).then(|| project.to_owned())
+}
+
+/// Unfolds content lines and reads the properties of each VEVENT
+fn parse_events(contents: &str) -> Result<Vec<Event>> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in contents.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+
+    let mut events = Vec::new();
+    let mut event: Option<Event> = None;
+    // Alarms and other components nested in an event have properties of their own
+    let mut nested = 0;
+
+    for line in &lines {
+        let Some((name, params, value)) = parse_property(line) else {
+            continue;
+        };
+
+        match (name.as_str(), value) {
+            ("BEGIN", "VEVENT") => event = Some(Event::default()),
+            ("END", "VEVENT") => events.extend(event.take()),
+            ("BEGIN", _) if event.is_some() => nested += 1,
+            ("END", _) if event.is_some() => nested -= 1,
+            _ => {}
+        }
+
+        let Some(event) = event.as_mut().filter(|_| nested == 0) else {
+            continue;
+        };
+
+        let time = || {
+            parse_time(value, &params)
+                .with_context(|| format!("Unable to parse {} \"{}\"", name, value))
+        };
+        match name.as_str() {
+            "UID" => event.uid = Some(value.to_owned()),
+            "SUMMARY" => event.summary = Some(unescape(value)),
+            "CATEGORIES" => event
+                .categories
+                .extend(split_list(value).into_iter().map(|c| unescape(&c))),
+            "DTSTART" => event.start = Some(time()?),
+            "DTEND" => event.end = Some(time()?),
+            "DURATION" => {
+                event.duration = Some(
+                    parse_duration(value)
+                        .with_context(|| format!("Unable to parse DURATION \"{}\"", value))?,
+                )
+            }
+            "RRULE" => event.rrule = Some(value.to_owned()),
+            "EXDATE" => {
+                for value in value.split(',') {
+                    event.exdates.push(
+                        parse_time(value, &params)
+                            .with_context(|| format!("Unable to parse EXDATE \"{}\"", value))?,
+                    );
+                }
+            }
+            "RECURRENCE-ID" => event.recurrence_id = Some(time()?),
+            "STATUS" => event.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            _ => {}
+        }
+    }
+
+    Ok(events)
+}
+
+/// Splits `NAME;PARAM=VALUE:VALUE` into its parts, respecting quoted parameters
+fn parse_property(line: &str) -> Option<(String, Params, &str)> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(i),
+        _ => None,
+    })?;
+
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.to_ascii_uppercase(), v.trim_matches('"').to_owned()))
+        .collect();
+
+    Some((name, params, value))
+}
+
+fn unescape(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, c == '\\') {
+            (_, true) => match chars.next() {
+                Some('n' | 'N') => output.push('\n'),
+                Some(other) => output.push(other),
+                None => {}
+            },
+            (c, false) => output.push(c),
+        }
+    }
+
+    output
+}
+
+/// Splits a comma separated list, leaving escaped commas alone
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = vec![String::new()];
+    let mut escaped = false;
+    for c in value.chars() {
+        match (c, escaped) {
+            (',', false) => items.push(String::new()),
+            _ => items.last_mut().unwrap().push(c),
+        }
+        escaped = c == '\\' && !escaped;
+    }
+
+    items
+}
+
+fn parse_time(value: &str, params: &[(String, String)]) -> Result<CalendarTime> {
+    let value = value.trim();
+    let is_date = params
+        .iter()
+        .any(|(k, v)| k == "VALUE" && v.eq_ignore_ascii_case("DATE"))
+        || value.len() == 8;
+
+    if is_date {
+        return Ok(CalendarTime::Date(NaiveDate::parse_from_str(
+            value, "%Y%m%d",
+        )?));
+    }
+
+    let (value, zone) = match value.strip_suffix('Z') {
+        Some(value) => (value, Zone::Utc),
+        None => match params.iter().find(|(k, _)| k == "TZID") {
+            // Zones that aren't in the tz database, often from Outlook, are read as local time
+            Some((_, tzid)) => (
+                value,
+                tzid.trim_start_matches('/')
+                    .parse::<Tz>()
+                    .map(Zone::Named)
+                    .unwrap_or(Zone::Floating),
+            ),
+            None => (value, Zone::Floating),
+        },
+    };
+
+    Ok(CalendarTime::DateTime(
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")?,
+        zone,
+    ))
+}
+
+/// Parses durations such as `PT1H30M` or `P1DT2H`, which `DURATION` uses
+fn parse_duration(value: &str) -> Result<Duration> {
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(value) => (-1, value),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let value = value.strip_prefix('P').context("Durations start with P")?;
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in value.chars() {
+        match c {
+            'T' => in_time = true,
+            '0'..='9' => number.push(c),
+            unit => {
+                let amount: i64 = number.parse().context("Missing number in duration")?;
+                number.clear();
+                total += match (unit, in_time) {
+                    ('W', false) => Duration::weeks(amount),
+                    ('D', false) => Duration::days(amount),
+                    ('H', true) => Duration::hours(amount),
+                    ('M', true) => Duration::minutes(amount),
+                    ('S', true) => Duration::seconds(amount),
+                    _ => bail!("Unknown duration unit '{}'", unit),
+                };
+            }
+        }
+    }
+
+    Ok(total * sign)
+}
+
+/// Lists the instants a recurring event occurs at, from its start up to `until_date`
+fn expand_rule(
+    rule: &str,
+    start: NaiveDateTime,
+    zone: Zone,
+    until_date: NaiveDate,
+) -> Result<Vec<DateTime<Utc>>> {
+    let mut freq = None;
+    let mut interval: u32 = 1;
+    let mut count: Option<usize> = None;
+    let mut until: Option<NaiveDateTime> = None;
+    let mut by_day: Vec<Weekday> = Vec::new();
+
+    for part in rule.split(';').filter(|p| !p.is_empty()) {
+        let (key, value) = part
+            .split_once('=')
+            .with_context(|| format!("Invalid rule part \"{}\"", part))?;
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => freq = Some(value.to_ascii_uppercase()),
+            "INTERVAL" => interval = value.parse().context("Invalid INTERVAL")?,
+            "COUNT" => count = Some(value.parse().context("Invalid COUNT")?),
+            "UNTIL" => {
+                until = Some(match parse_time(value, &[])? {
+                    CalendarTime::Date(date) => date.and_time(NaiveTime::MIN) + Duration::days(1),
+                    CalendarTime::DateTime(time, until_zone) => until_zone
+                        .instant(time)
+                        .map(|i| zone.wall_clock(i))
+                        .unwrap_or(time),
+                })
+            }
+            "BYDAY" => {
+                for day in value.split(',') {
+                    by_day.push(parse_weekday(day).with_context(|| {
+                        format!(
+                            "Only plain weekdays are supported in BYDAY, not \"{}\"",
+                            day
+                        )
+                    })?);
+                }
+            }
+            "WKST" => {}
+            other => bail!("The {} rule part isn't supported", other),
+        }
+    }
+
+    let freq = freq.context("The rule has no FREQ")?;
+    if !by_day.is_empty() && freq != "WEEKLY" {
+        bail!("BYDAY is only supported for WEEKLY rules");
+    }
+    let interval = interval.max(1);
+
+    // Candidate starts in order, before COUNT, UNTIL and the range are applied
+    let candidates: Box<dyn Iterator<Item = Option<NaiveDateTime>>> = match freq.as_str() {
+        "DAILY" => Box::new((0..).map(move |i| Some(start + Duration::days(i * interval as i64)))),
+        "WEEKLY" if by_day.is_empty() => {
+            Box::new((0..).map(move |i| Some(start + Duration::weeks(i * interval as i64))))
+        }
+        "WEEKLY" => {
+            let mut days: Vec<i64> = by_day
+                .iter()
+                .map(|d| d.num_days_from_monday() as i64)
+                .collect();
+            days.sort();
+            days.dedup();
+            let week_start = start - Duration::days(start.weekday().num_days_from_monday() as i64);
+
+            Box::new((0..).flat_map(move |week: i64| {
+                let days = days.clone();
+                days.into_iter().map(move |day| {
+                    Some(week_start + Duration::weeks(week * interval as i64) + Duration::days(day))
+                })
+            }))
+        }
+        "MONTHLY" => Box::new((0..).map(move |i| {
+            start
+                .date()
+                .checked_add_months(Months::new(i * interval))
+                .filter(|d| d.day() == start.day())
+                .map(|d| d.and_time(start.time()))
+        })),
+        "YEARLY" => Box::new((0..).map(move |i| {
+            start
+                .date()
+                .checked_add_months(Months::new(i * interval * 12))
+                .filter(|d| d.day() == start.day())
+                .map(|d| d.and_time(start.time()))
+        })),
+        other => bail!("FREQ={} isn't supported", other),
+    };
+
+    let mut occurrences = Vec::new();
+    let mut counted = 0;
+    for (checked, candidate) in candidates.enumerate() {
+        if checked >= MAX_OCCURRENCES {
+            bail!("The rule has more than {} occurrences", MAX_OCCURRENCES);
+        }
+
+        // Months without the start's day (the 31st, or 29 February) are skipped
+        let Some(candidate) = candidate else {
+            continue;
+        };
+        if candidate < start {
+            continue;
+        }
+        if candidate.date() > until_date || until.is_some_and(|u| candidate > u) {
+            break;
+        }
+        if count.is_some_and(|c| counted >= c) {
+            break;
+        }
+        counted += 1;
+
+        if let Some(instant) = zone.instant(candidate) {
+            occurrences.push(instant);
+        }
+    }
+
+    Ok(occurrences)
+}
+
+fn parse_weekday(day: &str) -> Option<Weekday> {
+    match day.trim().to_ascii_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
@@ -0,0 +1,290 @@
+//! Worklogs fetched from the Jira Cloud REST API.
+//!
+//! Issues are found with a `worklogAuthor` JQL search over the date range,
+//! then each issue's worklogs are filtered down to the chosen user. Hours are
+//! grouped under the issue key, or under the parent (epic) key when asked to.
+
+// The response types are only read by `fetch_entries`, and the tests
+#![cfg_attr(not(feature = "http"), allow(dead_code))]
+
+use super::TimeEntry;
+use crate::config::JiraConfig;
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate};
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// What a Jira worklog is billed under on the invoice
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum JiraGrouping {
+    /// The issue the time was logged against
+    #[default]
+    Issue,
+    /// The issue's parent, which is its epic for standard issues
+    Epic,
+}
+
+/// Connection details and filters for a Jira worklog import
+#[derive(Debug, Clone, PartialEq)]
+pub struct JiraOptions {
+    pub base_url: String,
+    pub email: String,
+    pub api_token: String,
+    /// Account ID whose worklogs are billed, defaulting to the authenticated user
+    pub account_id: Option<String>,
+    pub grouping: JiraGrouping,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SearchPage {
+    pub issues: Vec<Issue>,
+    pub next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Issue {
+    pub key: String,
+    #[serde(default)]
+    pub fields: IssueFields,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct IssueFields {
+    pub parent: Option<IssueReference>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct IssueReference {
+    pub key: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WorklogPage {
+    pub start_at: usize,
+    pub total: usize,
+    pub worklogs: Vec<Worklog>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Worklog {
+    pub author: Author,
+    pub started: String,
+    pub time_spent_seconds: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Author {
+    pub account_id: String,
+}
+
+impl Issue {
+    /// The project name this issue's time is billed under
+    pub(crate) fn billed_as(&self, grouping: JiraGrouping) -> &str {
+        match (grouping, &self.fields.parent) {
+            (JiraGrouping::Epic, Some(parent)) => &parent.key,
+            _ => &self.key,
+        }
+    }
+}
+
+/// Builds the JQL that finds issues the user logged work against in the range
+pub(crate) fn worklog_jql(account_id: Option<&str>, from: NaiveDate, to: NaiveDate) -> String {
+    let author = match account_id {
+        Some(id) => format!("\"{}\"", id.replace('"', "\\\"")),
+        None => "currentUser()".to_owned(),
+    };
+
+    format!(
+        "worklogAuthor = {} AND worklogDate >= \"{}\" AND worklogDate <= \"{}\"",
+        author, from, to
+    )
+}
+
+/// Keeps the worklogs by `account_id` started within the range
+pub(crate) fn worklog_entries(
+    project: &str,
+    worklogs: &[Worklog],
+    account_id: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<TimeEntry>> {
+    let mut entries = Vec::new();
+
+    for worklog in worklogs {
+        if worklog.author.account_id != account_id {
+            continue;
+        }
+
+        let started = DateTime::parse_from_str(&worklog.started, "%Y-%m-%dT%H:%M:%S%.f%z")
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Unable to parse worklog start \"{}\": {}",
+                    worklog.started,
+                    e
+                )
+            })?;
+        let date = started.date_naive();
+        if date < from || date > to {
+            continue;
+        }
+
+        entries.push(
+            TimeEntry::new(project, Duration::seconds(worklog.time_spent_seconds))
+                .started_at_instant(&started),
+        );
+    }
+
+    Ok(entries)
+}
+
+#[cfg(feature = "http")]
+pub fn fetch_entries(options: &JiraOptions) -> Result<Vec<TimeEntry>> {
+    use crate::http;
+    use anyhow::Context;
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Myself {
+        account_id: String,
+    }
+
+    let base_url = options.base_url.trim_end_matches('/');
+    let authorization = http::basic_auth(&options.email, &options.api_token);
+
+    let account_id = match &options.account_id {
+        Some(id) => id.clone(),
+        None => {
+            let myself: Myself = http::get_json(
+                &format!("{}/rest/api/3/myself", base_url),
+                &[("Authorization", &authorization)],
+                &[],
+            )
+            .context("Unable to look up the authenticated Jira user")?;
+            myself.account_id
+        }
+    };
+
+    let jql = worklog_jql(options.account_id.as_deref(), options.from, options.to);
+    let mut issues = Vec::new();
+    let mut next_page_token: Option<String> = None;
+    loop {
+        let mut query = vec![("jql", jql.as_str()), ("fields", "parent")];
+        if let Some(token) = &next_page_token {
+            query.push(("nextPageToken", token));
+        }
+
+        let page: SearchPage = http::get_json(
+            &format!("{}/rest/api/3/search/jql", base_url),
+            &[("Authorization", &authorization)],
+            &query,
+        )
+        .context("Unable to search Jira for worklogs")?;
+        issues.extend(page.issues);
+
+        match page.next_page_token {
+            Some(token) => next_page_token = Some(token),
+            None => break,
+        }
+    }
+
+    let mut entries = Vec::new();
+    for issue in &issues {
+        let mut start_at = 0;
+        loop {
+            let start = start_at.to_string();
+            let page: WorklogPage = http::get_json(
+                &format!("{}/rest/api/3/issue/{}/worklog", base_url, issue.key),
+                &[("Authorization", &authorization)],
+                &[("startAt", &start)],
+            )
+            .with_context(|| format!("Unable to fetch worklogs for {}", issue.key))?;
+
+            entries.extend(worklog_entries(
+                issue.billed_as(options.grouping),
+                &page.worklogs,
+                &account_id,
+                options.from,
+                options.to,
+            )?);
+
+            start_at = page.start_at + page.worklogs.len();
+            if page.worklogs.is_empty() || start_at >= page.total {
+                break;
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Command-line flags for the Jira source
+#[derive(clap::Args, Debug, Clone, Default)]
+#[command(next_help_heading = "Jira")]
+pub struct JiraArgs {
+    /// The Jira site to fetch worklogs from, e.g. https://acme.atlassian.net
+    #[arg(long, value_name = "URL")]
+    pub jira_url: Option<String>,
+
+    /// The email address used to authenticate with Jira
+    #[arg(long, value_name = "EMAIL", env = "JIRA_EMAIL")]
+    pub jira_email: Option<String>,
+
+    /// A Jira API token for the email address
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        env = "JIRA_API_TOKEN",
+        hide_env_values = true
+    )]
+    pub jira_token: Option<String>,
+
+    /// The account ID whose worklogs to bill (defaults to the authenticated user)
+    #[arg(long, value_name = "ACCOUNT_ID")]
+    pub jira_user: Option<String>,
+
+    /// Whether to bill hours under each issue or its epic
+    #[arg(long, value_enum, default_value_t)]
+    pub jira_group_by: JiraGrouping,
+}
+
+impl JiraArgs {
+    /// Checks the required connection details were given, falling back to the config file
+    pub fn options(
+        &self,
+        config: Option<&JiraConfig>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<JiraOptions> {
+        use anyhow::Context;
+
+        let config = config.unwrap_or(&JiraConfig::EMPTY);
+
+        Ok(JiraOptions {
+            base_url: self
+                .jira_url
+                .clone()
+                .or_else(|| config.url.clone())
+                .context("--jira-url is required for Jira")?,
+            email: self
+                .jira_email
+                .clone()
+                .or_else(|| config.email.clone())
+                .context("--jira-email (or JIRA_EMAIL) is required for Jira")?,
+            api_token: self
+                .jira_token
+                .clone()
+                .or_else(|| config.api_token.clone())
+                .context("--jira-token (or JIRA_API_TOKEN) is required for Jira")?,
+            account_id: self.jira_user.clone(),
+            grouping: self.jira_group_by,
+            from,
+            to,
+        })
+    }
+}
@@ -0,0 +1,45 @@
+//! Entries written as JSON, for scripts that generate time programmatically.
+//!
+//! The file is an array of entries. `duration` is either a number of hours
+//! or an `HH:MM:SS` string, and `date` and `notes` can be left out:
+//!
+//! ```json
+//! [
+//!   {"project": "Acme", "duration": 1.5, "date": "2024-06-03", "notes": "Kickoff"},
+//!   {"project": "Acme", "duration": "00:45:00"}
+//! ]
+//! ```
+
+use super::{TimeEntry, WrittenDuration};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonEntry {
+    project: String,
+    duration: WrittenDuration,
+    date: Option<NaiveDate>,
+    notes: Option<String>,
+}
+
+pub fn parse_entries(contents: &str) -> Result<Vec<TimeEntry>> {
+    let entries: Vec<JsonEntry> = serde_json::from_str(contents)?;
+
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let duration = entry
+                .duration
+                .to_duration()
+                .with_context(|| format!("Entry {} has an invalid duration", i + 1))?;
+
+            let mut time_entry = TimeEntry::new(&entry.project, duration);
+            time_entry.date = entry.date;
+            time_entry.notes = entry.notes;
+            Ok(time_entry)
+        })
+        .collect()
+}
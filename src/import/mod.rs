@@ -0,0 +1,355 @@
+//! Parsers for time-tracking formats other than the Toggl CSV export.
+//!
+//! Every parser turns its input into the same [`TimeEntry`] values that
+//! [`InvoiceBuilder::collect_time_entries`](crate::InvoiceBuilder::collect_time_entries)
+//! consumes, so the invoice maths doesn't care where the time came from.
+
+use anyhow::Context;
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone,
+};
+use clap::ValueEnum;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use std::borrow::Cow;
+
+pub mod activitywatch;
+pub mod caldav;
+pub mod clockify;
+pub mod fixed;
+pub mod git;
+pub mod google;
+pub mod ics;
+pub mod jira;
+pub mod json;
+pub mod org;
+pub mod remote;
+pub mod sqlite;
+pub mod timelog;
+pub mod timesheet;
+pub mod toggl;
+pub mod wakatime;
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
+
+#[cfg(test)]
+mod tests;
+
+/// A block of tracked time against a project
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeEntry {
+    pub project: String,
+    pub duration: Duration,
+    /// The day the time was tracked on, if the source records it
+    pub date: Option<NaiveDate>,
+    /// When the time started in local time, if the source records it
+    pub start: Option<NaiveDateTime>,
+    /// A description of the work, if the source records one
+    pub notes: Option<String>,
+    /// Whether the time was reconstructed rather than tracked, so it can be
+    /// billed at a discount
+    pub estimated: bool,
+    /// Whether the time is charged for, which is left off the invoice when it isn't
+    pub billable: bool,
+    /// Whether the time is left out of what's taxed, like a tax exempt project
+    pub tax_exempt: bool,
+    /// Labels the source gave the entry, for choosing which entries to bill
+    pub tags: Vec<String>,
+    /// The client the work was for, if the source records it
+    pub client: Option<String>,
+    /// The hourly rate the source charged the time at, which overrides
+    /// --pay-rate and the rate schedule
+    pub rate: Option<f64>,
+}
+
+impl TimeEntry {
+    pub fn new(project: &str, duration: Duration) -> Self {
+        Self {
+            project: project.to_owned(),
+            duration,
+            date: None,
+            start: None,
+            notes: None,
+            estimated: false,
+            billable: true,
+            tax_exempt: false,
+            tags: Vec::new(),
+            client: None,
+            rate: None,
+        }
+    }
+
+    /// Marks the time as reconstructed rather than tracked
+    pub fn as_estimate(mut self) -> Self {
+        self.estimated = true;
+        self
+    }
+
+    /// Records when the entry started, which also dates it
+    pub fn started_at(mut self, start: NaiveDateTime) -> Self {
+        self.date = Some(start.date());
+        self.start = Some(start);
+        self
+    }
+
+    /// Records when the entry started from a timestamp in any time zone
+    pub fn started_at_instant<Tz: TimeZone>(self, start: &DateTime<Tz>) -> Self {
+        self.started_at(start.with_timezone(&Local).naive_local())
+    }
+}
+
+impl TimeEntry {
+    /// Splits the entry at each midnight it spans, or into 24 hour days when
+    /// only its date or nothing at all is known
+    pub fn split_days(&self) -> Vec<TimeEntry> {
+        self.split_days_in(&Local)
+    }
+
+    /// Splits the entry at each midnight in the zone its start is in, so
+    /// days that gain or lose an hour to daylight saving get what they had
+    pub(crate) fn split_days_in<Tz: TimeZone>(&self, zone: &Tz) -> Vec<TimeEntry> {
+        let instant = |time: NaiveDateTime| zone.from_local_datetime(&time).earliest();
+        let mut days = Vec::new();
+        let mut remaining = self.duration;
+        let mut start = self.start;
+        let mut date = self.date;
+
+        while remaining > Duration::zero() {
+            let next_midnight = start
+                .and_then(|s| s.date().succ_opt())
+                .map(|d| d.and_time(NaiveTime::MIN));
+            let until_midnight = start
+                .zip(next_midnight)
+                .map(|(s, midnight)| match (instant(s), instant(midnight)) {
+                    (Some(s), Some(midnight)) => midnight - s,
+                    _ => midnight - s,
+                })
+                .unwrap_or(Duration::days(1));
+            let duration = remaining.min(until_midnight);
+
+            days.push(TimeEntry {
+                duration,
+                date,
+                start,
+                ..self.clone()
+            });
+
+            remaining -= duration;
+            date = date.and_then(|d| d.succ_opt());
+            start = next_midnight;
+        }
+
+        days
+    }
+}
+
+/// A row of an import that couldn't be read into an entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct BadRow {
+    /// The line the row starts on, counting from 1
+    pub line: u64,
+    /// The row's fields as read, joined by commas
+    pub content: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for BadRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.content.is_empty() {
+            true => write!(f, "Unable to read line {}: {}", self.line, self.reason),
+            false => write!(
+                f,
+                "Unable to read line {} \"{}\": {}",
+                self.line, self.content, self.reason
+            ),
+        }
+    }
+}
+
+/// What a lenient import left out, so that skipped rows can't go unnoticed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportReport {
+    /// The rows skipped, in the order they appear
+    pub skipped: Vec<BadRow>,
+}
+
+impl From<(String, Duration)> for TimeEntry {
+    fn from((project, duration): (String, Duration)) -> Self {
+        Self::new(&project, duration)
+    }
+}
+
+/// A duration written by hand, as a number of hours or an `HH:MM:SS` string
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(untagged)]
+pub(crate) enum WrittenDuration {
+    Hours(f64),
+    Clock(String),
+}
+
+impl WrittenDuration {
+    pub(crate) fn to_duration(&self) -> anyhow::Result<Duration> {
+        match self {
+            WrittenDuration::Hours(hours) if hours.is_finite() && *hours >= 0.0 => {
+                Ok(Duration::seconds((hours * 3600.0).round() as i64))
+            }
+            WrittenDuration::Hours(hours) => anyhow::bail!("{} isn't a number of hours", hours),
+            WrittenDuration::Clock(clock) => crate::InvoiceBuilder::parse_duration_str(clock),
+        }
+    }
+}
+
+/// Decodes a text file from the encoding asked for, or else the one its byte
+/// order mark names
+///
+/// Without either, UTF-16 is recognised by its zero bytes and anything that
+/// isn't valid UTF-8 is read as Windows-1252, which Excel often saves CSV in.
+pub fn decode<'a>(contents: &'a [u8], encoding: Option<&'static Encoding>) -> Cow<'a, str> {
+    if let Some(encoding) = encoding {
+        return encoding.decode_with_bom_removal(contents).0;
+    }
+    if let Some((encoding, bom)) = Encoding::for_bom(contents) {
+        return encoding.decode_without_bom_handling(&contents[bom..]).0;
+    }
+
+    let sample = &contents[..contents.len().min(1024) & !1];
+    let zeros = |offset: usize| {
+        sample
+            .iter()
+            .skip(offset)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count()
+    };
+    if !sample.is_empty() && zeros(1) * 3 > sample.len() / 2 && zeros(0) == 0 {
+        return UTF_16LE.decode_without_bom_handling(contents).0;
+    }
+    if !sample.is_empty() && zeros(0) * 3 > sample.len() / 2 && zeros(1) == 0 {
+        return UTF_16BE.decode_without_bom_handling(contents).0;
+    }
+
+    match std::str::from_utf8(contents) {
+        Ok(text) => Cow::Borrowed(text),
+        Err(_) => WINDOWS_1252.decode_without_bom_handling(contents).0,
+    }
+}
+
+/// Timestamp layouts read in local time, after RFC 3339 and RFC 2822 which carry their own offset
+const LOCAL_TIMESTAMP_FORMATS: [&str; 6] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%d.%m.%Y %H:%M:%S",
+    "%d.%m.%Y %H:%M",
+];
+
+/// Parses a timestamp exported by a time tracker
+///
+/// Timestamps without an offset are in local time, so the time between two
+/// of them accounts for daylight saving changes.
+pub(crate) fn parse_timestamp(str: &str) -> anyhow::Result<DateTime<Local>> {
+    parse_timestamp_in(str, &Local)
+}
+
+/// Parses a timestamp, reading those without an offset in the given zone
+pub(crate) fn parse_timestamp_in<Tz: TimeZone>(
+    str: &str,
+    zone: &Tz,
+) -> anyhow::Result<DateTime<Tz>> {
+    let str = str.trim();
+    if let Some(time) = parse_offset_timestamp(str) {
+        return Ok(time.with_timezone(zone));
+    }
+
+    let naive = parse_wall_clock(str)
+        .with_context(|| format!("\"{}\" isn't a timestamp like 2024-06-03 09:30", str))?;
+    zone.from_local_datetime(&naive)
+        .earliest()
+        .with_context(|| format!("{} doesn't exist in the local time zone", naive))
+}
+
+fn parse_offset_timestamp(str: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(str)
+        .or_else(|_| DateTime::parse_from_rfc2822(str))
+        .ok()
+}
+
+fn parse_wall_clock(str: &str) -> Option<NaiveDateTime> {
+    LOCAL_TIMESTAMP_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(str, format).ok())
+}
+
+/// Parses the day an entry was tracked on, written like 2024-06-03 or
+/// 03.06.2024, or as a timestamp on that day
+///
+/// A timestamp's day is the one written, in its own offset, so an entry
+/// logged late in the evening in another zone isn't moved to the next day.
+pub(crate) fn parse_date(str: &str) -> anyhow::Result<NaiveDate> {
+    let str = str.trim();
+    ["%Y-%m-%d", "%d.%m.%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(str, format).ok())
+        .or_else(|| parse_offset_timestamp(str).map(|t| t.date_naive()))
+        .or_else(|| parse_wall_clock(str).map(|t| t.date()))
+        .with_context(|| format!("\"{}\" isn't a date like 2024-06-03", str))
+}
+
+/// Looks up an encoding by a label such as `windows-1252` or `utf-16le`
+pub fn parse_encoding(label: &str) -> anyhow::Result<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+        .with_context(|| format!("Unknown encoding \"{}\"", label))
+}
+
+/// A service that time entries can be fetched from instead of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Source {
+    /// Window activity from a local ActivityWatch server, mapped to projects
+    Activitywatch,
+    /// Events from a CalDAV calendar, such as on Nextcloud or Fastmail
+    Caldav,
+    /// The current user's entries from the Clockify API
+    Clockify,
+    /// Hours estimated from the commits in git repositories
+    Git,
+    /// Timed events from a Google Calendar
+    Google,
+    /// Worklogs from the Jira Cloud REST API
+    Jira,
+    /// Entries selected from a local SQLite database with --query
+    Sqlite,
+    /// The detailed report from the Toggl Track Reports API
+    Toggl,
+    /// Daily coding time per project from the WakaTime summaries API
+    Wakatime,
+}
+
+/// Resolves the date range to fetch, filling in missing ends with month boundaries
+///
+/// With neither end given the range is the month containing `today`. A lone
+/// `from` runs to the end of its month, and a lone `to` starts at the
+/// beginning of its month.
+pub fn date_range(
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    today: NaiveDate,
+) -> (NaiveDate, NaiveDate) {
+    match (from, to) {
+        (Some(from), Some(to)) => (from, to),
+        (Some(from), None) => (from, end_of_month(from)),
+        (None, Some(to)) => (start_of_month(to), to),
+        (None, None) => (start_of_month(today), end_of_month(today)),
+    }
+}
+
+fn start_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+fn end_of_month(date: NaiveDate) -> NaiveDate {
+    start_of_month(date)
+        .checked_add_months(Months::new(1))
+        .and_then(|d| d.pred_opt())
+        .unwrap_or(date)
+}
@@ -0,0 +1,222 @@
+//! Emacs org-mode `CLOCK:` entries and exported clock tables.
+//!
+//! Clocked time is attributed to the top-level heading it was logged under,
+//! so sub-tasks roll up into their project:
+//!
+//! ```org
+//! * TODO Acme Website                                    :client:
+//! ** Landing page
+//!    :LOGBOOK:
+//!    CLOCK: [2024-06-03 Mon 09:00]--[2024-06-03 Mon 10:30] =>  1:30
+//!    :END:
+//! ```
+//!
+//! Files without any `CLOCK:` lines are read as an exported clocktable
+//! instead, using the time of each top-level row.
+
+use super::TimeEntry;
+use anyhow::{bail, Context, Result};
+use chrono::{Duration, NaiveDateTime};
+
+const DEFAULT_TODO_KEYWORDS: [&str; 2] = ["TODO", "DONE"];
+
+/// Parses the clocked time in an org document into time entries
+pub fn parse_entries(contents: &str) -> Result<Vec<TimeEntry>> {
+    let keywords = todo_keywords(contents);
+
+    let mut entries = Vec::new();
+    let mut project: Option<String> = None;
+
+    for (index, line) in contents.lines().enumerate() {
+        if let Some((level, title)) = parse_heading(line) {
+            if level == 1 {
+                project = Some(clean_heading_title(title, &keywords));
+            }
+            continue;
+        }
+
+        let Some(clock) = line.trim_start().strip_prefix("CLOCK:") else {
+            continue;
+        };
+
+        let Some((start, duration)) = parse_clock_line(clock)
+            .with_context(|| format!("Unable to parse CLOCK entry on line {}", index + 1))?
+        else {
+            // The clock is still running, there is nothing to bill yet
+            continue;
+        };
+
+        match &project {
+            Some(project) => entries.push(TimeEntry::new(project, duration).started_at(start)),
+            None => bail!("CLOCK entry on line {} is not under a heading", index + 1),
+        }
+    }
+
+    if entries.is_empty() {
+        return parse_clocktable(contents);
+    }
+
+    Ok(entries)
+}
+
+/// Collects the TODO keywords declared with `#+TODO:` and friends, plus the defaults
+fn todo_keywords(contents: &str) -> Vec<String> {
+    let mut keywords: Vec<String> = DEFAULT_TODO_KEYWORDS
+        .iter()
+        .map(|k| k.to_string())
+        .collect();
+
+    for line in contents.lines() {
+        let line = line.trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        if !matches!(
+            key.to_ascii_uppercase().as_str(),
+            "#+TODO" | "#+SEQ_TODO" | "#+TYP_TODO"
+        ) {
+            continue;
+        }
+
+        keywords.extend(
+            value
+                .split_whitespace()
+                .filter(|k| *k != "|")
+                // Strip fast-access keys such as `WAIT(w@/!)`
+                .map(|k| k.split('(').next().unwrap_or(k).to_owned()),
+        );
+    }
+
+    keywords
+}
+
+/// Returns the level and raw title of an org heading line
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '*').count();
+    if level == 0 {
+        return None;
+    }
+
+    line[level..]
+        .strip_prefix(' ')
+        .map(|title| (level, title.trim()))
+}
+
+/// Strips TODO keywords, priority cookies and tags from a heading title
+fn clean_heading_title(title: &str, keywords: &[String]) -> String {
+    let mut words: Vec<&str> = title.split_whitespace().collect();
+
+    if words
+        .first()
+        .is_some_and(|w| keywords.iter().any(|k| k == w))
+    {
+        words.remove(0);
+    }
+    if words
+        .first()
+        .is_some_and(|w| w.starts_with("[#") && w.ends_with(']'))
+    {
+        words.remove(0);
+    }
+    if words
+        .last()
+        .is_some_and(|w| w.len() > 1 && w.starts_with(':') && w.ends_with(':'))
+    {
+        words.pop();
+    }
+
+    words.join(" ")
+}
+
+/// Parses the part of a CLOCK line after `CLOCK:`, returning `None` for running clocks
+fn parse_clock_line(clock: &str) -> Result<Option<(NaiveDateTime, Duration)>> {
+    let (range, total) = match clock.trim().split_once("=>") {
+        Some((range, total)) => (range, Some(total)),
+        None => (clock, None),
+    };
+
+    let Some((start, end)) = range.trim().split_once("--") else {
+        return Ok(None);
+    };
+
+    let start = parse_timestamp(start)?;
+    if let Some(total) = total {
+        return Ok(Some((start, parse_clock_duration(total.trim())?)));
+    }
+
+    let end = parse_timestamp(end)?;
+    if end < start {
+        bail!("Clock ends before it starts");
+    }
+
+    Ok(Some((start, end - start)))
+}
+
+/// Parses an inactive org timestamp such as `[2024-06-03 Mon 09:00]`
+fn parse_timestamp(str: &str) -> Result<NaiveDateTime> {
+    let inner = str
+        .trim()
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .with_context(|| format!("Timestamp \"{}\" is not an inactive timestamp", str))?;
+
+    let parts: Vec<&str> = inner.split_whitespace().collect();
+    let (date, time) = match parts.as_slice() {
+        [date, _, time] | [date, time] => (*date, *time),
+        _ => bail!("Unable to parse timestamp \"{}\"", str),
+    };
+
+    NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M")
+        .with_context(|| format!("Unable to parse timestamp \"{}\"", str))
+}
+
+/// Parses org's `H:MM` clock sums, where the hours can exceed a day
+fn parse_clock_duration(str: &str) -> Result<Duration> {
+    let (hours, minutes) = str
+        .split_once(':')
+        .with_context(|| format!("Clock duration \"{}\" is not in H:MM form", str))?;
+
+    let hours: i64 = hours
+        .parse()
+        .with_context(|| format!("Unable to parse hour string \"{}\"", hours))?;
+    let minutes: i64 = minutes
+        .parse()
+        .with_context(|| format!("Unable to parse minutes string \"{}\"", minutes))?;
+
+    Ok(Duration::hours(hours) + Duration::minutes(minutes))
+}
+
+/// Reads the top-level rows of an exported clocktable, which carry no dates
+fn parse_clocktable(contents: &str) -> Result<Vec<TimeEntry>> {
+    let mut entries = Vec::new();
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if !line.starts_with('|') || line.starts_with("|-") {
+            continue;
+        }
+
+        let cells: Vec<&str> = line.trim_matches('|').split('|').map(str::trim).collect();
+        let Some(position) = cells.iter().position(|c| parse_clock_duration(c).is_ok()) else {
+            continue;
+        };
+
+        // Nested rows are prefixed with `\_` and are already counted in their parent
+        let headline = cells[..position]
+            .iter()
+            .rev()
+            .find(|c| !c.is_empty())
+            .copied()
+            .unwrap_or_default();
+        if headline.is_empty() || headline.starts_with("\\_") || headline.starts_with('*') {
+            continue;
+        }
+
+        entries.push(TimeEntry::new(
+            headline,
+            parse_clock_duration(cells[position])?,
+        ));
+    }
+
+    Ok(entries)
+}
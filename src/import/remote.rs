@@ -0,0 +1,94 @@
+//! Timesheets downloaded from an http(s) URL given to `--file`.
+//!
+//! The file is saved to the temporary directory under the last segment of
+//! the URL's path, so its extension still picks the parser, then removed once
+//! it has been imported. Links without an extension are read as CSV. The
+//! copy is only written to a file this run created, readable only by the
+//! user, so a file or link already at that path is never written through.
+
+// The naming is only used by `download`, and the tests
+#![cfg_attr(not(feature = "http"), allow(dead_code))]
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// A downloaded copy of a remote timesheet
+#[derive(Debug)]
+pub struct Download {
+    path: PathBuf,
+}
+
+impl Download {
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Deletes the downloaded copy
+    pub fn remove(self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Saves a download to a new file in the temporary directory, refusing to
+/// reuse one that's already there
+pub(crate) fn save(name: &str, contents: &[u8]) -> Result<Download> {
+    let path = std::env::temp_dir().join(name);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options
+        .open(&path)
+        .with_context(|| format!("Unable to create \"{:?}\" for the download", path))?;
+    let download = Download { path };
+    if let Err(err) = file.write_all(contents) {
+        download.remove();
+        return Err(err).context("Unable to save the download");
+    }
+
+    Ok(download)
+}
+
+/// The file name a URL's contents are saved under, keeping its extension
+pub(crate) fn file_name(url: &str) -> String {
+    let url = url.split(['?', '#']).next().unwrap_or(url);
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let last = without_scheme
+        .split_once('/')
+        .and_then(|(_, path)| path.trim_end_matches('/').rsplit('/').next())
+        .filter(|s| !s.is_empty())
+        .unwrap_or("timesheet");
+
+    let safe: String = last
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                true => c,
+                false => '_',
+            },
+        )
+        .collect();
+    format!("pint-rs-{}-{}", std::process::id(), safe)
+}
+
+#[cfg(feature = "http")]
+pub fn download(url: &str, token: Option<&str>) -> Result<Download> {
+    let authorization = token.map(|t| format!("Bearer {}", t));
+    let headers: Vec<(&str, &str)> = authorization
+        .iter()
+        .map(|a| ("Authorization", a.as_str()))
+        .collect();
+
+    let contents = crate::http::get_bytes(url, &headers)
+        .with_context(|| format!("Unable to download \"{}\"", url))?;
+
+    save(&file_name(url), &contents)
+}
+
+#[cfg(not(feature = "http"))]
+pub fn download(url: &str, token: Option<&str>) -> Result<Download> {
+    let _ = (url, token);
+    Err(crate::missing_feature("Downloading files", "http"))
+}
@@ -0,0 +1,90 @@
+//! Entries selected from a local SQLite database with a query of your own.
+//!
+//! The query's first column is the project and its second the number of
+//! seconds worked. An optional third column dates the entry, either as a
+//! `YYYY-MM-DD` date or a `YYYY-MM-DD HH:MM:SS` local start time:
+//!
+//! ```sql
+//! SELECT project, seconds, date(started) FROM entries WHERE billable
+//! ```
+
+// The row parsing is only used by `query_entries`, and the tests
+#![cfg_attr(not(feature = "sqlite"), allow(dead_code, unused_imports))]
+
+use super::TimeEntry;
+use anyhow::{Context, Result};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+#[cfg(feature = "sqlite")]
+use rusqlite::{types::ValueRef, Connection, OpenFlags};
+use std::path::Path;
+
+/// Command-line flags for the SQLite source
+#[derive(clap::Args, Debug, Clone, Default)]
+#[command(next_help_heading = "SQLite")]
+pub struct SqliteArgs {
+    /// The database file to query
+    #[arg(long, value_name = "FILE")]
+    pub sqlite_db: Option<std::path::PathBuf>,
+
+    /// A query returning the project, seconds and optionally date of each entry
+    #[arg(long, value_name = "SQL")]
+    pub query: Option<String>,
+}
+
+/// Runs the query against the database, which is opened read-only
+#[cfg(feature = "sqlite")]
+pub fn query_entries(database: &Path, query: &str) -> Result<Vec<TimeEntry>> {
+    let connection = Connection::open_with_flags(database, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Unable to open database \"{:?}\"", database))?;
+
+    connection_entries(&connection, query)
+}
+
+#[cfg(feature = "sqlite")]
+pub(crate) fn connection_entries(connection: &Connection, query: &str) -> Result<Vec<TimeEntry>> {
+    let mut statement = connection
+        .prepare(query)
+        .context("Unable to prepare the query")?;
+    if statement.column_count() < 2 {
+        anyhow::bail!("The query has to return at least a project and a number of seconds");
+    }
+    let dated = statement.column_count() > 2;
+
+    let mut rows = statement.query([]).context("Unable to run the query")?;
+    let mut entries = Vec::new();
+    while let Some(row) = rows.next()? {
+        let project: String = row
+            .get(0)
+            .with_context(|| format!("Row {} has no project", entries.len() + 1))?;
+        let seconds = match row.get_ref(1)? {
+            ValueRef::Integer(seconds) => seconds,
+            ValueRef::Real(seconds) => seconds.round() as i64,
+            other => anyhow::bail!(
+                "Row {} has {:?} rather than a number of seconds",
+                entries.len() + 1,
+                other.data_type()
+            ),
+        };
+
+        let mut entry = TimeEntry::new(&project, Duration::seconds(seconds));
+        if dated {
+            if let Some(text) = row.get::<_, Option<String>>(2)? {
+                entry = dated_entry(entry, &text)
+                    .with_context(|| format!("Unable to parse the date \"{}\"", text))?;
+            }
+        }
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+fn dated_entry(mut entry: TimeEntry, text: &str) -> Result<TimeEntry> {
+    let text = text.trim();
+
+    if let Ok(start) = NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S") {
+        return Ok(entry.started_at(start));
+    }
+    entry.date = Some(NaiveDate::parse_from_str(text, "%Y-%m-%d")?);
+    Ok(entry)
+}
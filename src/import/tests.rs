@@ -0,0 +1,988 @@
+use super::*;
+
+fn project_durations(entries: &[TimeEntry]) -> Vec<(String, Duration)> {
+    entries
+        .iter()
+        .map(|e| (e.project.clone(), e.duration))
+        .collect()
+}
+
+#[test]
+fn test_org_clock_entries_roll_up_to_top_level_heading() -> anyhow::Result<()> {
+    const ORG: &str = "\
+#+TODO: TODO NEXT(n) | DONE
+* NEXT [#A] Acme Website                                   :client:
+** Landing page
+   :LOGBOOK:
+   CLOCK: [2024-06-03 Mon 09:00]--[2024-06-03 Mon 10:30] =>  1:30
+   CLOCK: [2024-06-04 Tue 13:00]--[2024-06-04 Tue 13:45] =>  0:45
+   :END:
+* Internal
+  CLOCK: [2024-06-05 Wed 22:00]--[2024-06-06 Thu 01:00] =>  3:00
+";
+
+    let entries = org::parse_entries(ORG)?;
+
+    let expected = vec![
+        ("Acme Website".to_owned(), Duration::minutes(90)),
+        ("Acme Website".to_owned(), Duration::minutes(45)),
+        ("Internal".to_owned(), Duration::hours(3)),
+    ];
+    assert_eq!(project_durations(&entries), expected);
+    assert_eq!(entries[2].date, NaiveDate::from_ymd_opt(2024, 6, 5));
+
+    Ok(())
+}
+
+#[test]
+fn test_org_clock_without_sum_uses_timestamps() -> anyhow::Result<()> {
+    const ORG: &str = "\
+* Project
+CLOCK: [2024-06-03 Mon 09:00]--[2024-06-03 Mon 11:15]
+CLOCK: [2024-06-04 Tue 09:00]
+";
+
+    let entries = org::parse_entries(ORG)?;
+
+    assert_eq!(
+        project_durations(&entries),
+        vec![("Project".to_owned(), Duration::minutes(135))]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_org_clock_outside_heading() {
+    const ORG: &str = "CLOCK: [2024-06-03 Mon 09:00]--[2024-06-03 Mon 10:00] =>  1:00\n";
+
+    let entries = org::parse_entries(ORG);
+
+    assert!(entries.is_err());
+}
+
+#[test]
+fn test_org_clocktable_uses_top_level_rows() -> anyhow::Result<()> {
+    const ORG: &str = "\
+#+BEGIN: clocktable :scope file :maxlevel 2
+#+CAPTION: Clock summary at [2024-06-30 Sun 12:00]
+| Headline     | Time    |      |
+|--------------+---------+------|
+| *Total time* | *27:30* |      |
+|--------------+---------+------|
+| Acme Website | 25:00   |      |
+| \\_  Landing  |         | 1:30 |
+| Internal     | 2:30    |      |
+#+END:
+";
+
+    let entries = org::parse_entries(ORG)?;
+
+    let expected = vec![
+        ("Acme Website".to_owned(), Duration::hours(25)),
+        ("Internal".to_owned(), Duration::minutes(150)),
+    ];
+    assert_eq!(project_durations(&entries), expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_timelog_pairs_check_ins_and_outs() -> anyhow::Result<()> {
+    const TIMELOG: &str = "\
+i 2024/06/03 09:00:00 Acme:Website  landing page
+o 2024/06/03 10:30:00
+; lunch
+i 2024/06/03 13:00:00 Internal
+O 2024/06/03 13:20:00
+i 2024/06/03 14:00:00 Acme:Website
+";
+
+    let entries = timelog::parse_entries(TIMELOG)?;
+
+    let expected = vec![
+        ("Acme:Website".to_owned(), Duration::minutes(90)),
+        ("Internal".to_owned(), Duration::minutes(20)),
+    ];
+    assert_eq!(project_durations(&entries), expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_timelog_check_out_without_check_in() {
+    const TIMELOG: &str = "o 2024/06/03 10:30:00\n";
+
+    let entries = timelog::parse_entries(TIMELOG);
+
+    assert!(entries.is_err());
+}
+
+#[test]
+fn test_timelog_double_check_in() {
+    const TIMELOG: &str = "\
+i 2024/06/03 09:00:00 Acme
+i 2024/06/03 10:00:00 Internal
+";
+
+    let entries = timelog::parse_entries(TIMELOG);
+
+    assert!(entries.is_err());
+}
+
+#[test]
+fn test_date_range_defaults_to_current_month() {
+    let today = NaiveDate::from_ymd_opt(2024, 2, 14).unwrap();
+
+    let range = date_range(None, None, today);
+
+    let expected = (
+        NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(),
+    );
+    assert_eq!(range, expected);
+}
+
+#[test]
+fn test_date_range_fills_in_missing_end() {
+    let today = NaiveDate::from_ymd_opt(2024, 2, 14).unwrap();
+    let from = NaiveDate::from_ymd_opt(2023, 12, 10).unwrap();
+
+    let range = date_range(Some(from), None, today);
+
+    assert_eq!(
+        range,
+        (from, NaiveDate::from_ymd_opt(2023, 12, 31).unwrap())
+    );
+}
+
+#[test]
+fn test_jira_worklogs_filtered_by_author_and_range() -> anyhow::Result<()> {
+    const PAGE: &str = r#"{
+        "startAt": 0,
+        "maxResults": 5000,
+        "total": 3,
+        "worklogs": [
+            {"author": {"accountId": "me"}, "started": "2024-06-03T09:00:00.000+0000", "timeSpentSeconds": 5400},
+            {"author": {"accountId": "someone"}, "started": "2024-06-03T11:00:00.000+0000", "timeSpentSeconds": 3600},
+            {"author": {"accountId": "me"}, "started": "2024-07-01T09:00:00.000+0200", "timeSpentSeconds": 1800}
+        ]
+    }"#;
+    let page: jira::WorklogPage = serde_json::from_str(PAGE)?;
+
+    let entries = jira::worklog_entries(
+        "ABC-1",
+        &page.worklogs,
+        "me",
+        NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 6, 30).unwrap(),
+    )?;
+
+    assert_eq!(
+        project_durations(&entries),
+        vec![("ABC-1".to_owned(), Duration::minutes(90))]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_jira_epic_grouping_falls_back_to_issue() -> anyhow::Result<()> {
+    const PAGE: &str = r#"{
+        "issues": [
+            {"key": "ABC-2", "fields": {"parent": {"key": "ABC-1"}}},
+            {"key": "ABC-3", "fields": {}}
+        ]
+    }"#;
+    let page: jira::SearchPage = serde_json::from_str(PAGE)?;
+
+    let projects: Vec<&str> = page
+        .issues
+        .iter()
+        .map(|i| i.billed_as(jira::JiraGrouping::Epic))
+        .collect();
+
+    assert_eq!(projects, vec!["ABC-1", "ABC-3"]);
+    assert!(page.next_page_token.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_toggl_report_rows_named_after_projects() -> anyhow::Result<()> {
+    const ROWS: &str = r#"[
+        {"project_id": 11, "description": "Landing page", "time_entries": [
+            {"id": 1, "seconds": 5400, "start": "2024-06-03T09:00:00+00:00"},
+            {"id": 2, "seconds": 1800, "start": "2024-06-04T09:00:00+00:00"}
+        ]},
+        {"project_id": null, "description": "Email", "time_entries": [
+            {"id": 3, "seconds": 600, "start": "2024-06-04T10:00:00+00:00"}
+        ]},
+        {"project_id": 11, "description": "Running", "time_entries": [
+            {"id": 4, "seconds": -1717405200, "start": "2024-06-04T11:00:00+00:00"}
+        ]}
+    ]"#;
+    let rows: Vec<toggl::ReportRow> = serde_json::from_str(ROWS)?;
+    let projects = std::collections::HashMap::from([(11, "Acme Website".to_owned())]);
+
+    let entries = toggl::report_entries(&rows, &projects);
+
+    let expected = vec![
+        ("Acme Website".to_owned(), Duration::minutes(90)),
+        ("Acme Website".to_owned(), Duration::minutes(30)),
+        (toggl::NO_PROJECT.to_owned(), Duration::minutes(10)),
+    ];
+    assert_eq!(project_durations(&entries), expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_clockify_entries_skip_running_timers() -> anyhow::Result<()> {
+    const ENTRIES: &str = r#"[
+        {"id": "a", "description": "Landing page", "projectId": "p1",
+         "timeInterval": {"start": "2024-06-03T09:00:00Z", "end": "2024-06-03T10:30:00Z", "duration": "PT1H30M"},
+         "project": {"id": "p1", "name": "Acme Website"}},
+        {"id": "b", "description": "Email", "projectId": null,
+         "timeInterval": {"start": "2024-06-03T11:00:00Z", "end": "2024-06-03T11:15:00Z", "duration": "PT15M"},
+         "project": null},
+        {"id": "c", "description": "Running", "projectId": "p1",
+         "timeInterval": {"start": "2024-06-03T12:00:00Z", "end": null, "duration": null},
+         "project": {"id": "p1", "name": "Acme Website"}}
+    ]"#;
+    let entries: Vec<clockify::ClockifyEntry> = serde_json::from_str(ENTRIES)?;
+
+    let entries = clockify::time_entries(&entries);
+
+    let expected = vec![
+        ("Acme Website".to_owned(), Duration::minutes(90)),
+        (clockify::NO_PROJECT.to_owned(), Duration::minutes(15)),
+    ];
+    assert_eq!(project_durations(&entries), expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_git_log_grouped_by_trailer() -> anyhow::Result<()> {
+    const LOG: &str = "\
+2024-06-03T09:00:00+02:00\x1fAcme Website\x1e
+2024-06-03T09:30:00+02:00\x1f\x1e
+";
+
+    let commits = git::parse_log(LOG, "website", git::GitGrouping::Trailer)?;
+
+    let projects: Vec<&str> = commits.iter().map(|c| c.project.as_str()).collect();
+    assert_eq!(projects, vec!["Acme Website", "website"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_git_sessions_split_on_long_gaps() -> anyhow::Result<()> {
+    const LOG: &str = "\
+2024-06-03T10:00:00+00:00\x1f\x1e
+2024-06-03T09:00:00+00:00\x1f\x1e
+2024-06-03T15:00:00+00:00\x1f\x1e
+";
+    let commits = git::parse_log(LOG, "website", git::GitGrouping::Repo)?;
+
+    let entries = git::sessions(&commits, Duration::hours(2), Duration::minutes(30));
+
+    let expected = vec![
+        ("website".to_owned(), Duration::minutes(90)),
+        ("website".to_owned(), Duration::minutes(30)),
+    ];
+    assert_eq!(project_durations(&entries), expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_wakatime_summaries_dated_per_day() -> anyhow::Result<()> {
+    const SUMMARIES: &str = r#"{
+        "data": [
+            {"range": {"date": "2024-06-03", "start": "2024-06-03T00:00:00Z", "end": "2024-06-03T23:59:59Z"},
+             "projects": [
+                {"name": "bee-rs", "total_seconds": 5400.4, "text": "1 hr 30 mins"},
+                {"name": "dotfiles", "total_seconds": 0.0, "text": "0 secs"}
+             ]},
+            {"range": {"date": "2024-06-04", "start": "2024-06-04T00:00:00Z", "end": "2024-06-04T23:59:59Z"},
+             "projects": [{"name": "bee-rs", "total_seconds": 900.0, "text": "15 mins"}]}
+        ],
+        "cumulative_total": {"seconds": 6300.4}
+    }"#;
+    let summaries: wakatime::Summaries = serde_json::from_str(SUMMARIES)?;
+
+    let entries = wakatime::summary_entries(&summaries);
+
+    let expected = vec![
+        ("bee-rs".to_owned(), Duration::minutes(90)),
+        ("bee-rs".to_owned(), Duration::minutes(15)),
+    ];
+    assert_eq!(project_durations(&entries), expected);
+    assert_eq!(entries[1].date, NaiveDate::from_ymd_opt(2024, 6, 4));
+
+    Ok(())
+}
+
+#[test]
+fn test_activitywatch_first_matching_rule_wins() -> anyhow::Result<()> {
+    const MAPPING: &str = r#"
+[[rule]]
+project = "Acme Website"
+title = "acme"
+
+[[rule]]
+project = "Internal"
+app = "^code$"
+"#;
+
+    let mapping = activitywatch::Mapping::parse(MAPPING)?;
+
+    assert_eq!(
+        mapping.project_for("Code", "acme/index.html"),
+        Some("Acme Website")
+    );
+    assert_eq!(mapping.project_for("code", "notes.md"), Some("Internal"));
+    assert_eq!(mapping.project_for("firefox", "News"), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_activitywatch_rule_needs_a_pattern() {
+    const MAPPING: &str = "[[rule]]\nproject = \"Acme\"\n";
+
+    let mapping = activitywatch::Mapping::parse(MAPPING);
+
+    assert!(mapping.is_err());
+}
+
+#[test]
+fn test_activitywatch_afk_time_is_removed() -> anyhow::Result<()> {
+    const WINDOW: &str = r#"[
+        {"id": 1, "timestamp": "2024-06-03T09:00:00+00:00", "duration": 3600.0, "data": {"app": "code", "title": "acme"}},
+        {"id": 2, "timestamp": "2024-06-03T10:00:00+00:00", "duration": 600.0, "data": {"app": "firefox", "title": "News"}}
+    ]"#;
+    const AFK: &str = r#"[
+        {"id": 3, "timestamp": "2024-06-03T09:00:00+00:00", "duration": 1800.0, "data": {"status": "not-afk"}},
+        {"id": 4, "timestamp": "2024-06-03T09:30:00+00:00", "duration": 900.0, "data": {"status": "afk"}},
+        {"id": 5, "timestamp": "2024-06-03T09:45:00+00:00", "duration": 3600.0, "data": {"status": "not-afk"}}
+    ]"#;
+    let mapping =
+        activitywatch::Mapping::parse("[[rule]]\nproject = \"Acme\"\ntitle = \"acme\"\n")?;
+    let window: Vec<activitywatch::Event> = serde_json::from_str(WINDOW)?;
+    let afk: Vec<activitywatch::Event> = serde_json::from_str(AFK)?;
+
+    let active = activitywatch::active_spans(&afk);
+    let entries = activitywatch::mapped_entries(&window, Some(&active), &mapping);
+
+    assert_eq!(
+        project_durations(&entries),
+        vec![("Acme".to_owned(), Duration::minutes(45))]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_ics_weekly_rule_expanded_within_range() -> anyhow::Result<()> {
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r
+BEGIN:VEVENT\r
+UID:standup\r
+SUMMARY:Acme\r
+DTSTART:20240603T120000Z\r
+DTEND:20240603T123000Z\r
+RRULE:FREQ=WEEKLY;BYDAY=MO,WE\r
+EXDATE:20240605T120000Z\r
+BEGIN:VALARM\r
+SUMMARY:Reminder\r
+END:VALARM\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:standup\r
+RECURRENCE-ID:20240610T120000Z\r
+SUMMARY:Acme\r
+DTSTART:20240610T130000Z\r
+DURATION:PT1H\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+    let from = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    let to = NaiveDate::from_ymd_opt(2024, 6, 12).unwrap();
+
+    let entries = ics::parse_entries(CALENDAR, &ics::IcsArgs::default(), from, to)?;
+
+    // 5 June is excluded and 10 June was moved to an hour long meeting
+    let dates: Vec<_> = entries.iter().filter_map(|e| e.date).collect();
+    assert_eq!(
+        dates,
+        vec![
+            NaiveDate::from_ymd_opt(2024, 6, 3).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 6, 12).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 6, 10).unwrap(),
+        ]
+    );
+    assert_eq!(
+        entries.iter().map(|e| e.duration).sum::<Duration>(),
+        Duration::minutes(120)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_ics_prefix_selects_and_names_projects() -> anyhow::Result<()> {
+    const CALENDAR: &str = "BEGIN:VEVENT
+SUMMARY:Bill: Acme \\, Ltd
+DTSTART;TZID=Europe/Malta:20240603T140000
+DTEND;TZID=Europe/Malta:20240603T
+ 153000
+END:VEVENT
+BEGIN:VEVENT
+SUMMARY:Lunch
+DTSTART:20240603T120000Z
+DTEND:20240603T130000Z
+END:VEVENT
+BEGIN:VEVENT
+SUMMARY:Bill: Holiday
+DTSTART;VALUE=DATE:20240604
+DTEND;VALUE=DATE:20240605
+END:VEVENT
+";
+    let args = ics::IcsArgs {
+        ics_prefix: Some("Bill:".to_owned()),
+        ..Default::default()
+    };
+    let from = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    let to = NaiveDate::from_ymd_opt(2024, 6, 30).unwrap();
+
+    let entries = ics::parse_entries(CALENDAR, &args, from, to)?;
+
+    assert_eq!(
+        project_durations(&entries),
+        vec![("Acme , Ltd".to_owned(), Duration::minutes(90))]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_ics_unsupported_rule_is_refused() {
+    const CALENDAR: &str = "BEGIN:VEVENT
+SUMMARY:Acme
+DTSTART:20240603T120000Z
+DTEND:20240603T130000Z
+RRULE:FREQ=MONTHLY;BYSETPOS=-1;BYDAY=FR
+END:VEVENT
+";
+    let from = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    let to = NaiveDate::from_ymd_opt(2024, 6, 30).unwrap();
+
+    let entries = ics::parse_entries(CALENDAR, &ics::IcsArgs::default(), from, to);
+
+    assert!(entries.is_err());
+}
+
+#[test]
+fn test_google_events_skip_all_day_and_declined() -> anyhow::Result<()> {
+    const EVENTS: &str = r#"{
+        "items": [
+            {"status": "confirmed", "summary": "Acme: Planning", "start": {"dateTime": "2024-06-03T10:00:00+02:00"}, "end": {"dateTime": "2024-06-03T11:30:00+02:00"}},
+            {"status": "confirmed", "summary": "Acme: Offsite", "start": {"date": "2024-06-04"}, "end": {"date": "2024-06-05"}},
+            {"status": "confirmed", "summary": "Acme: Review", "start": {"dateTime": "2024-06-05T10:00:00Z"}, "end": {"dateTime": "2024-06-05T11:00:00Z"},
+             "attendees": [{"email": "me@example.com", "self": true, "responseStatus": "declined"}]},
+            {"status": "confirmed", "summary": "Dentist", "start": {"dateTime": "2024-06-06T10:00:00Z"}, "end": {"dateTime": "2024-06-06T11:00:00Z"}}
+        ]
+    }"#;
+    let page: google::EventsPage = serde_json::from_str(EVENTS)?;
+
+    let entries = google::event_entries(&page.items, Some("Acme:"))?;
+
+    assert_eq!(
+        project_durations(&entries),
+        vec![("Planning".to_owned(), Duration::minutes(90))]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_caldav_multistatus_calendar_objects() -> anyhow::Result<()> {
+    const MULTISTATUS: &str = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:href>/calendars/me/work/a.ics</d:href>
+    <d:propstat><d:prop><cal:calendar-data>BEGIN:VCALENDAR
+BEGIN:VEVENT
+SUMMARY:Acme &amp; Co
+DTSTART:20240603T120000Z
+DTEND:20240603T130000Z
+END:VEVENT
+END:VCALENDAR
+</cal:calendar-data></d:prop></d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/calendars/me/work/b.ics</d:href>
+    <d:propstat><d:prop><calendar-data xmlns="urn:ietf:params:xml:ns:caldav"><![CDATA[BEGIN:VCALENDAR
+BEGIN:VEVENT
+SUMMARY:Globex
+DTSTART:20240604T120000Z
+DURATION:PT45M
+END:VEVENT
+END:VCALENDAR]]></calendar-data></d:prop></d:propstat>
+  </d:response>
+</d:multistatus>"#;
+    let from = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    let to = NaiveDate::from_ymd_opt(2024, 6, 30).unwrap();
+
+    let objects = caldav::calendar_objects(MULTISTATUS);
+    let entries = caldav::object_entries(&objects, &ics::IcsArgs::default(), from, to)?;
+
+    assert_eq!(
+        project_durations(&entries),
+        vec![
+            ("Acme & Co".to_owned(), Duration::hours(1)),
+            ("Globex".to_owned(), Duration::minutes(45)),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_json_entries_accept_hours_or_clock_durations() -> anyhow::Result<()> {
+    const ENTRIES: &str = r#"[
+        {"project": "Acme", "duration": 1.5, "date": "2024-06-03", "notes": "Kickoff"},
+        {"project": "Globex", "duration": "00:45:00"}
+    ]"#;
+
+    let entries = json::parse_entries(ENTRIES)?;
+
+    assert_eq!(
+        project_durations(&entries),
+        vec![
+            ("Acme".to_owned(), Duration::minutes(90)),
+            ("Globex".to_owned(), Duration::minutes(45)),
+        ]
+    );
+    assert_eq!(entries[0].date, NaiveDate::from_ymd_opt(2024, 6, 3));
+    assert_eq!(entries[0].notes.as_deref(), Some("Kickoff"));
+
+    Ok(())
+}
+
+#[test]
+fn test_json_entries_reject_unknown_fields() {
+    const ENTRIES: &str = r#"[{"project": "Acme", "hours": 1.5}]"#;
+
+    let entries = json::parse_entries(ENTRIES);
+
+    assert!(entries.is_err());
+}
+
+#[test]
+fn test_toml_timesheet_nested_by_date() -> anyhow::Result<()> {
+    const TIMESHEET: &str = r#"
+[2024-06-03]
+Acme = 1.5
+Globex = ["00:45:00", 0.25]
+
+["2024-06-04"]
+Acme = 2
+"#;
+
+    let entries = timesheet::parse_toml(TIMESHEET)?;
+
+    assert_eq!(
+        project_durations(&entries),
+        vec![
+            ("Acme".to_owned(), Duration::minutes(90)),
+            ("Globex".to_owned(), Duration::minutes(45)),
+            ("Globex".to_owned(), Duration::minutes(15)),
+            ("Acme".to_owned(), Duration::hours(2)),
+        ]
+    );
+    assert_eq!(entries[3].date, NaiveDate::from_ymd_opt(2024, 6, 4));
+
+    Ok(())
+}
+
+#[test]
+fn test_yaml_timesheet_nested_by_date() -> anyhow::Result<()> {
+    const TIMESHEET: &str = "
+2024-06-03:
+  Acme: 1.5
+  Globex: \"00:45:00\"
+";
+
+    let entries = timesheet::parse_yaml(TIMESHEET)?;
+
+    assert_eq!(
+        project_durations(&entries),
+        vec![
+            ("Acme".to_owned(), Duration::minutes(90)),
+            ("Globex".to_owned(), Duration::minutes(45)),
+        ]
+    );
+    assert!(timesheet::parse_yaml("2024-06-03:\n  Acme: -1\n").is_err());
+
+    Ok(())
+}
+
+#[cfg(feature = "xlsx")]
+#[test]
+fn test_xlsx_rows_read_like_csv() -> anyhow::Result<()> {
+    use calamine::{Data, ExcelDateTime, ExcelDateTimeType, Range};
+
+    let mut range = Range::new((0, 0), (2, 3));
+    range.set_value((0, 0), Data::String("Project".to_owned()));
+    range.set_value((0, 3), Data::String("Duration".to_owned()));
+    range.set_value((1, 0), Data::String("Acme".to_owned()));
+    range.set_value(
+        (1, 3),
+        Data::DateTime(ExcelDateTime::new(
+            0.0625,
+            ExcelDateTimeType::TimeDelta,
+            false,
+        )),
+    );
+    range.set_value((2, 0), Data::String("Globex".to_owned()));
+    range.set_value((2, 3), Data::String("00:45:00".to_owned()));
+
+    let entries = xlsx::sheet_entries(&range, &crate::Columns::default())?;
+
+    assert_eq!(
+        project_durations(&entries),
+        vec![
+            ("Acme".to_owned(), Duration::minutes(90)),
+            ("Globex".to_owned(), Duration::minutes(45)),
+        ]
+    );
+
+    let columns = crate::Columns {
+        project: "project".parse()?,
+        duration: "Duration".parse()?,
+        ..Default::default()
+    };
+    assert_eq!(xlsx::sheet_entries(&range, &columns)?.len(), 2);
+
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_sqlite_query_rows_become_entries() -> anyhow::Result<()> {
+    let connection = rusqlite::Connection::open_in_memory()?;
+    connection.execute_batch(
+        "CREATE TABLE entries (project TEXT, seconds INTEGER, started TEXT);
+         INSERT INTO entries VALUES ('Acme', 5400, '2024-06-03 09:00:00');
+         INSERT INTO entries VALUES ('Globex', 2700, NULL);",
+    )?;
+
+    let entries = sqlite::connection_entries(
+        &connection,
+        "SELECT project, seconds, started FROM entries ORDER BY project",
+    )?;
+
+    assert_eq!(
+        project_durations(&entries),
+        vec![
+            ("Acme".to_owned(), Duration::minutes(90)),
+            ("Globex".to_owned(), Duration::minutes(45)),
+        ]
+    );
+    assert_eq!(entries[0].date, NaiveDate::from_ymd_opt(2024, 6, 3));
+    assert!(sqlite::connection_entries(&connection, "SELECT project FROM entries").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_remote_file_name_keeps_extension() {
+    let name = remote::file_name("https://example.com/reports/june.xlsx?token=abc#sheet");
+    assert!(name.ends_with("-june.xlsx"));
+
+    let name = remote::file_name("https://example.com/");
+    assert!(name.ends_with("-timesheet"));
+}
+
+#[test]
+fn test_remote_download_only_written_to_a_new_file() -> anyhow::Result<()> {
+    let name = remote::file_name("https://example.com/save-test.csv");
+    let download = remote::save(&name, b"Project,Duration\n")?;
+    assert_eq!(std::fs::read(download.path())?, b"Project,Duration\n");
+
+    // A file already at the path, such as a planted link, isn't written to
+    assert!(remote::save(&name, b"secret").is_err());
+    let path = download.path().clone();
+    download.remove();
+    assert!(!path.exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_decode_csv_encodings() -> anyhow::Result<()> {
+    let utf16: Vec<u8> = [0xFF, 0xFE]
+        .into_iter()
+        .chain("Café,01:00:00".encode_utf16().flat_map(u16::to_le_bytes))
+        .collect();
+    let bomless_utf16: Vec<u8> = "Café,01:00:00"
+        .encode_utf16()
+        .flat_map(u16::to_be_bytes)
+        .collect();
+
+    assert_eq!(
+        decode(b"\xEF\xBB\xBFCaf\xC3\xA9,01:00:00", None),
+        "Café,01:00:00"
+    );
+    assert_eq!(decode(&utf16, None), "Café,01:00:00");
+    assert_eq!(decode(&bomless_utf16, None), "Café,01:00:00");
+    assert_eq!(decode(b"Caf\xE9,01:00:00", None), "Café,01:00:00");
+    assert_eq!(decode(b"Caf\xE9", Some(parse_encoding("latin1")?)), "Café");
+    assert!(parse_encoding("klingon").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_fixed_width_entries() -> anyhow::Result<()> {
+    const DUMP: &str = "\
+PROJECT             DATE      HOURS
+
+Acme Website        20240603  01:30:00
+Globex              20240604  0.25
+";
+    let layout: fixed::FixedWidth = "0-20, 30-38".parse()?;
+
+    let entries = fixed::parse_entries(DUMP, &layout, crate::DurationFormat::Auto)?;
+    assert_eq!(
+        project_durations(&entries),
+        vec![
+            ("Acme Website".to_owned(), Duration::minutes(90)),
+            ("Globex".to_owned(), Duration::minutes(15)),
+        ]
+    );
+
+    // Only the first line can be a header
+    let missing = format!("{}Initech             20240604\n", DUMP);
+    assert!(fixed::parse_entries(&missing, &layout, crate::DurationFormat::Auto).is_err());
+    assert!("20-0,30-38".parse::<fixed::FixedWidth>().is_err());
+
+    Ok(())
+}
+
+/// Period math around daylight saving changes, offsets that aren't whole
+/// hours, and the ends of months, since a mistake here changes invoice totals
+mod period_math {
+    use super::*;
+    use chrono_tz::{Australia, Europe};
+
+    fn span<Tz: TimeZone>(start: &str, end: &str, zone: &Tz) -> anyhow::Result<Duration> {
+        Ok(parse_timestamp_in(end, zone)? - parse_timestamp_in(start, zone)?)
+    }
+
+    fn day(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_span_loses_an_hour_when_clocks_go_forward() -> anyhow::Result<()> {
+        let london = Europe::London;
+
+        assert_eq!(
+            span("2024-03-31 00:30", "2024-03-31 02:30", &london)?,
+            Duration::hours(1)
+        );
+        assert!(parse_timestamp_in("2024-03-31 01:30", &london).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_span_gains_an_hour_when_clocks_go_back() -> anyhow::Result<()> {
+        let london = Europe::London;
+
+        assert_eq!(
+            span("2024-10-27 00:30", "2024-10-27 02:30", &london)?,
+            Duration::hours(3)
+        );
+        // The repeated hour is read as its first occurrence, still in summer time
+        assert_eq!(
+            parse_timestamp_in("2024-10-27 01:30", &london)?.to_rfc3339(),
+            "2024-10-27T01:30:00+01:00"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_half_hour_offsets() -> anyhow::Result<()> {
+        let adelaide = Australia::Adelaide;
+
+        // Adelaide moves from +10:30 to +09:30 at 03:00 on the first Sunday of April
+        assert_eq!(
+            span("2024-04-07 01:30", "2024-04-07 03:30", &adelaide)?,
+            Duration::hours(3)
+        );
+        assert_eq!(
+            span(
+                "2024-06-03T09:00:00+05:30",
+                "2024-06-03T08:30:00+04:00",
+                &adelaide
+            )?,
+            Duration::hours(1)
+        );
+        assert_eq!(
+            span(
+                "2024-06-03T09:00:00+05:45",
+                "2024-06-03T13:45:00+09:30",
+                &adelaide
+            )?,
+            Duration::hours(1)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timestamp_dates_are_the_day_written() -> anyhow::Result<()> {
+        assert_eq!(parse_date("2024-06-30T23:30:00+05:30")?, day(2024, 6, 30));
+        assert_eq!(parse_date("2024-06-30T23:30:00-09:30")?, day(2024, 6, 30));
+        assert_eq!(
+            parse_date("Sun, 30 Jun 2024 23:30:00 +1400")?,
+            day(2024, 6, 30)
+        );
+        assert_eq!(parse_date("30.06.2024 23:30")?, day(2024, 6, 30));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_days_follows_daylight_saving() {
+        let london = Europe::London;
+        let start = |day: NaiveDate| day.and_hms_opt(23, 0, 0).unwrap();
+        let durations = |entry: TimeEntry| -> Vec<Duration> {
+            entry
+                .split_days_in(&london)
+                .iter()
+                .map(|e| e.duration)
+                .collect()
+        };
+
+        // 31 March is 23 hours long, and 27 October 25
+        let spring =
+            TimeEntry::new("Acme", Duration::hours(25)).started_at(start(day(2024, 3, 30)));
+        assert_eq!(
+            durations(spring),
+            [Duration::hours(1), Duration::hours(23), Duration::hours(1)]
+        );
+        let autumn =
+            TimeEntry::new("Acme", Duration::hours(26)).started_at(start(day(2024, 10, 26)));
+        assert_eq!(durations(autumn), [Duration::hours(1), Duration::hours(25)]);
+    }
+
+    #[test]
+    fn test_ics_recurrence_keeps_wall_clock_time_across_daylight_saving() -> anyhow::Result<()> {
+        const CALENDAR: &str = "BEGIN:VEVENT
+SUMMARY:Acme
+DTSTART;TZID=Europe/Malta:20240325T090000
+DTEND;TZID=Europe/Malta:20240325T100000
+RRULE:FREQ=WEEKLY;COUNT=2
+END:VEVENT
+BEGIN:VEVENT
+SUMMARY:Globex
+DTSTART;TZID=Europe/Malta:20240331T013000
+DTEND;TZID=Europe/Malta:20240331T033000
+END:VEVENT
+";
+        let entries = ics::parse_entries(
+            CALENDAR,
+            &ics::IcsArgs::default(),
+            day(2024, 3, 1),
+            day(2024, 4, 30),
+        )?;
+
+        assert_eq!(
+            project_durations(&entries),
+            vec![
+                ("Acme".to_owned(), Duration::hours(1)),
+                ("Acme".to_owned(), Duration::hours(1)),
+                ("Globex".to_owned(), Duration::hours(1)),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_month_ranges_end_on_their_last_day() {
+        assert_eq!(
+            date_range(None, None, day(2024, 2, 10)),
+            (day(2024, 2, 1), day(2024, 2, 29))
+        );
+        assert_eq!(
+            date_range(None, None, day(2023, 2, 10)),
+            (day(2023, 2, 1), day(2023, 2, 28))
+        );
+        assert_eq!(
+            date_range(None, None, day(2024, 12, 31)),
+            (day(2024, 12, 1), day(2024, 12, 31))
+        );
+        assert_eq!(
+            date_range(Some(day(2024, 1, 31)), None, day(2024, 6, 1)),
+            (day(2024, 1, 31), day(2024, 1, 31))
+        );
+        assert_eq!(
+            date_range(None, Some(day(2024, 3, 1)), day(2024, 6, 1)),
+            (day(2024, 3, 1), day(2024, 3, 1))
+        );
+    }
+
+    #[test]
+    fn test_last_month_crosses_the_year() {
+        let args = crate::Args {
+            last_month: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            args.date_bounds(day(2025, 1, 1)),
+            (Some(day(2024, 12, 1)), Some(day(2024, 12, 31)))
+        );
+        assert_eq!(
+            args.date_bounds(day(2024, 3, 31)),
+            (Some(day(2024, 2, 1)), Some(day(2024, 2, 29)))
+        );
+    }
+
+    #[test]
+    fn test_month_filter_keeps_its_first_and_last_days() {
+        let args = crate::Args {
+            month: Some(day(2024, 2, 1)),
+            ..Default::default()
+        };
+        let entries: Vec<TimeEntry> = [
+            day(2024, 1, 31),
+            day(2024, 2, 1),
+            day(2024, 2, 29),
+            day(2024, 3, 1),
+        ]
+        .into_iter()
+        .map(|date| TimeEntry {
+            date: Some(date),
+            ..TimeEntry::new("Acme", Duration::hours(1))
+        })
+        .collect();
+
+        let mut builder = crate::InvoiceBuilder::new(&args);
+        builder.collect_time_entries(&entries);
+
+        assert_eq!(
+            builder.entries().iter().map(|e| e.date).collect::<Vec<_>>(),
+            [Some(day(2024, 2, 1)), Some(day(2024, 2, 29))]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! The `timeclock.el` timelog format, also read by ledger and hledger.
+//!
+//! Each `i` line checks in to a project and the next `o` (or `O`) line checks
+//! out again, the pair becoming one entry:
+//!
+//! ```text
+//! i 2024/06/03 09:00:00 Acme:Website  landing page
+//! o 2024/06/03 10:30:00
+//! ```
+//!
+//! The project runs until two spaces or a tab, anything after is a description.
+
+use super::TimeEntry;
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDateTime;
+
+/// Parses a timelog into time entries, ignoring a trailing open check-in
+pub fn parse_entries(contents: &str) -> Result<Vec<TimeEntry>> {
+    let mut entries = Vec::new();
+    let mut checked_in: Option<(String, NaiveDateTime)> = None;
+
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let Some((code, rest)) = line.split_once(' ') else {
+            continue;
+        };
+
+        match code {
+            "i" => {
+                if checked_in.is_some() {
+                    bail!("Check-in on line {} while already checked in", line_number);
+                }
+
+                let (time, project) = parse_timestamp(rest)
+                    .with_context(|| format!("Unable to parse check-in on line {}", line_number))?;
+                let project = project
+                    .split('\t')
+                    .next()
+                    .and_then(|p| p.split("  ").next())
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                    .with_context(|| format!("Check-in on line {} has no project", line_number))?;
+
+                checked_in = Some((project.to_owned(), time));
+            }
+            "o" | "O" => {
+                let (end, _) = parse_timestamp(rest).with_context(|| {
+                    format!("Unable to parse check-out on line {}", line_number)
+                })?;
+                let Some((project, start)) = checked_in.take() else {
+                    bail!("Check-out on line {} without a check-in", line_number);
+                };
+                if end < start {
+                    bail!("Check-out on line {} is before its check-in", line_number);
+                }
+
+                entries.push(TimeEntry::new(&project, end - start).started_at(start));
+            }
+            // Comments plus the `b`/`h` codes, which carry no time
+            _ => continue,
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Splits a `YYYY/MM/DD HH:MM:SS` timestamp from the rest of the line
+fn parse_timestamp(str: &str) -> Result<(NaiveDateTime, &str)> {
+    let str = str.trim_start();
+    let mut parts = str.splitn(3, ' ');
+    let date = parts.next().unwrap_or_default();
+    let time = parts.next().unwrap_or_default();
+    let rest = parts.next().unwrap_or_default();
+
+    let timestamp =
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y/%m/%d %H:%M:%S")
+            .or_else(|_| {
+                NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M:%S")
+            })
+            .with_context(|| format!("Unable to parse timestamp \"{} {}\"", date, time))?;
+
+    Ok((timestamp, rest))
+}
@@ -0,0 +1,64 @@
+//! Hand-written timesheets in TOML or YAML, nested by date then project.
+//!
+//! Each project's time on a day is a number of hours or an `HH:MM:SS`
+//! string, or a list of them when it was logged in several sittings:
+//!
+//! ```toml
+//! [2024-06-03]
+//! Acme = 1.5
+//! Globex = ["00:45:00", 0.25]
+//! ```
+//!
+//! ```yaml
+//! 2024-06-03:
+//!   Acme: 1.5
+//!   Globex: ["00:45:00", 0.25]
+//! ```
+
+use super::{TimeEntry, WrittenDuration};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+type Timesheet = BTreeMap<NaiveDate, BTreeMap<String, Sittings>>;
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Sittings {
+    One(WrittenDuration),
+    Many(Vec<WrittenDuration>),
+}
+
+pub fn parse_toml(contents: &str) -> Result<Vec<TimeEntry>> {
+    entries(toml::from_str(contents)?)
+}
+
+pub fn parse_yaml(contents: &str) -> Result<Vec<TimeEntry>> {
+    entries(serde_yaml::from_str(contents)?)
+}
+
+fn entries(timesheet: Timesheet) -> Result<Vec<TimeEntry>> {
+    let mut entries = Vec::new();
+
+    for (date, projects) in timesheet {
+        for (project, sittings) in projects {
+            let sittings = match sittings {
+                Sittings::One(duration) => vec![duration],
+                Sittings::Many(durations) => durations,
+            };
+
+            for duration in sittings {
+                let duration = duration
+                    .to_duration()
+                    .with_context(|| format!("Invalid time for \"{}\" on {}", project, date))?;
+
+                let mut entry = TimeEntry::new(&project, duration);
+                entry.date = Some(date);
+                entries.push(entry);
+            }
+        }
+    }
+
+    Ok(entries)
+}
@@ -0,0 +1,149 @@
+//! Time entries fetched from the Toggl Track Reports API.
+//!
+//! The detailed report is searched for the date range and each entry is
+//! billed under its project's name, looked up from the workspace's projects.
+
+// The response types are only read by `fetch_entries`, and the tests
+#![cfg_attr(not(feature = "http"), allow(dead_code))]
+
+use super::TimeEntry;
+#[cfg(feature = "http")]
+use anyhow::Result;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The project name Toggl itself shows for entries without a project
+pub const NO_PROJECT: &str = "Without project";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TogglOptions {
+    pub api_token: String,
+    pub workspace_id: u64,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SearchRequest {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub page_size: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_row_number: Option<u64>,
+}
+
+/// One row of the detailed report, grouping entries with the same description
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReportRow {
+    pub project_id: Option<u64>,
+    pub time_entries: Vec<ReportTimeEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReportTimeEntry {
+    pub seconds: i64,
+    pub start: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Project {
+    pub id: u64,
+    pub name: String,
+}
+
+/// Flattens report rows into entries named after their projects
+pub(crate) fn report_entries(
+    rows: &[ReportRow],
+    projects: &HashMap<u64, String>,
+) -> Vec<TimeEntry> {
+    rows.iter()
+        .flat_map(|row| {
+            let project = row
+                .project_id
+                .and_then(|id| projects.get(&id))
+                .map(String::as_str)
+                .unwrap_or(NO_PROJECT);
+
+            row.time_entries
+                .iter()
+                // Running timers are reported with a negative duration
+                .filter(|e| e.seconds > 0)
+                .map(move |e| {
+                    let entry = TimeEntry::new(project, Duration::seconds(e.seconds));
+                    match &e.start {
+                        Some(start) => entry.started_at_instant(start),
+                        None => entry,
+                    }
+                })
+        })
+        .collect()
+}
+
+#[cfg(feature = "http")]
+pub fn fetch_entries(options: &TogglOptions) -> Result<Vec<TimeEntry>> {
+    use crate::http;
+    use anyhow::Context;
+
+    const PAGE_SIZE: usize = 50;
+    const PROJECTS_PAGE_SIZE: usize = 200;
+
+    let authorization = http::basic_auth(&options.api_token, "api_token");
+
+    let mut projects = HashMap::new();
+    for page in 1.. {
+        let per_page = PROJECTS_PAGE_SIZE.to_string();
+        let page_number = page.to_string();
+        let page: Vec<Project> = http::get_json(
+            &format!(
+                "https://api.track.toggl.com/api/v9/workspaces/{}/projects",
+                options.workspace_id
+            ),
+            &[("Authorization", &authorization)],
+            &[
+                ("active", "both"),
+                ("per_page", &per_page),
+                ("page", &page_number),
+            ],
+        )
+        .context("Unable to fetch Toggl projects")?;
+
+        let count = page.len();
+        projects.extend(page.into_iter().map(|p| (p.id, p.name)));
+        if count < PROJECTS_PAGE_SIZE {
+            break;
+        }
+    }
+
+    let mut entries = Vec::new();
+    let mut first_row_number = None;
+    loop {
+        let request = SearchRequest {
+            start_date: options.from,
+            end_date: options.to,
+            page_size: PAGE_SIZE,
+            first_row_number,
+        };
+        let (rows, headers): (Vec<ReportRow>, _) = http::post_json(
+            &format!(
+                "https://api.track.toggl.com/reports/api/v3/workspace/{}/search/time_entries",
+                options.workspace_id
+            ),
+            &[("Authorization", &authorization)],
+            &request,
+        )
+        .context("Unable to fetch the Toggl detailed report")?;
+
+        entries.extend(report_entries(&rows, &projects));
+
+        first_row_number = headers
+            .get("X-Next-Row-Number")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok());
+        if first_row_number.is_none() {
+            break;
+        }
+    }
+
+    Ok(entries)
+}
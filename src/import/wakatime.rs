@@ -0,0 +1,91 @@
+//! Daily per-project coding time from the WakaTime summaries API.
+//!
+//! Each project's total for each day becomes one entry. Self-hosted servers
+//! with a WakaTime-compatible API, such as Wakapi, work by changing `api_url`.
+
+// The response types are only read by `fetch_entries`, and the tests
+#![cfg_attr(not(feature = "http"), allow(dead_code))]
+
+use super::TimeEntry;
+#[cfg(feature = "http")]
+use anyhow::Result;
+use chrono::{Duration, NaiveDate};
+use serde::Deserialize;
+
+pub const DEFAULT_API_URL: &str = "https://wakatime.com/api/v1";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WakaTimeOptions {
+    pub api_key: String,
+    pub api_url: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Summaries {
+    pub data: Vec<DaySummary>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DaySummary {
+    pub range: SummaryRange,
+    #[serde(default)]
+    pub projects: Vec<ProjectSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SummaryRange {
+    pub date: NaiveDate,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ProjectSummary {
+    pub name: String,
+    pub total_seconds: f64,
+}
+
+/// Turns each day's project totals into entries dated on that day
+pub(crate) fn summary_entries(summaries: &Summaries) -> Vec<TimeEntry> {
+    summaries
+        .data
+        .iter()
+        .flat_map(|day| {
+            day.projects
+                .iter()
+                .filter(|p| p.total_seconds > 0.0)
+                .map(move |p| {
+                    let mut entry =
+                        TimeEntry::new(&p.name, Duration::seconds(p.total_seconds.round() as i64));
+                    entry.date = Some(day.range.date);
+                    entry
+                })
+        })
+        .collect()
+}
+
+#[cfg(feature = "http")]
+pub fn fetch_entries(options: &WakaTimeOptions) -> Result<Vec<TimeEntry>> {
+    use crate::http;
+    use anyhow::Context;
+    use base64::Engine;
+
+    let authorization = format!(
+        "Basic {}",
+        base64::engine::general_purpose::STANDARD.encode(&options.api_key)
+    );
+    let start = options.from.to_string();
+    let end = options.to.to_string();
+
+    let summaries: Summaries = http::get_json(
+        &format!(
+            "{}/users/current/summaries",
+            options.api_url.trim_end_matches('/')
+        ),
+        &[("Authorization", &authorization)],
+        &[("start", &start), ("end", &end)],
+    )
+    .context("Unable to fetch WakaTime summaries")?;
+
+    Ok(summary_entries(&summaries))
+}
@@ -0,0 +1,104 @@
+//! Time logs in Excel workbooks (and the other spreadsheets `calamine` reads).
+//!
+//! Rows are read like CSV files: by default the project from the first
+//! column and the duration from the fourth, skipping the header row. A
+//! duration can be a time-formatted cell, an `HH:MM:SS` string, or a number
+//! of hours.
+
+use super::{TimeEntry, WrittenDuration};
+use crate::{Columns, DurationFormat};
+use anyhow::{Context, Result};
+use calamine::{Data, DataType, Range, Reader};
+use chrono::Duration;
+use std::path::Path;
+
+/// Reads the entries from the named sheet, or the first sheet in the workbook
+pub fn read_entries(path: &Path, sheet: Option<&str>, columns: &Columns) -> Result<Vec<TimeEntry>> {
+    let mut workbook = calamine::open_workbook_auto(path)?;
+
+    let range = match sheet {
+        Some(sheet) => workbook
+            .worksheet_range(sheet)
+            .with_context(|| format!("Unable to read sheet \"{}\"", sheet))?,
+        None => workbook
+            .worksheet_range_at(0)
+            .context("The workbook has no sheets")??,
+    };
+
+    sheet_entries(&range, columns)
+}
+
+pub(crate) fn sheet_entries(range: &Range<Data>, columns: &Columns) -> Result<Vec<TimeEntry>> {
+    let headers: Vec<String> = range
+        .rows()
+        .next()
+        .map(|row| row.iter().map(|c| c.to_string()).collect())
+        .unwrap_or_default();
+    if columns.span.is_some() {
+        anyhow::bail!("--start-column and --end-column only apply to CSV files");
+    }
+    let project_column = columns.project.position(&headers)?;
+    let duration_column = columns.duration.position(&headers)?;
+    let billable_column = columns.billable_position(&headers)?;
+    let tax_exempt_column = columns.tax_exempt_position(&headers)?;
+    let tags_column = columns.tags_position(&headers)?;
+    let notes_column = columns.notes_position(&headers)?;
+    let rate_column = columns.rate_position(&headers)?;
+
+    let mut entries = Vec::new();
+    for (i, row) in range.rows().enumerate().skip(1) {
+        let (Some(project), Some(duration)) = (row.get(project_column), row.get(duration_column))
+        else {
+            continue;
+        };
+        if project.is_empty() && duration.is_empty() {
+            continue;
+        }
+
+        let duration = cell_duration(duration, columns.duration_format)
+            .with_context(|| format!("Unable to read the duration in row {}", i + 1))?;
+        let mut entry = TimeEntry::new(&project.to_string(), duration);
+        if let Some(flag) = billable_column.and_then(|b| row.get(b)) {
+            entry.billable = crate::parse_billable(&flag.to_string())
+                .with_context(|| format!("Unable to read whether row {} is billable", i + 1))?;
+        }
+        if let Some(flag) = tax_exempt_column.and_then(|t| row.get(t)) {
+            entry.tax_exempt = crate::parse_exempt(&flag.to_string())
+                .with_context(|| format!("Unable to read whether row {} is tax exempt", i + 1))?;
+        }
+        if let Some(list) = tags_column.and_then(|t| row.get(t)) {
+            entry.tags = crate::parse_tags(&list.to_string());
+        }
+        entry.notes = notes_column
+            .and_then(|n| row.get(n))
+            .map(|n| n.to_string().trim().to_owned())
+            .filter(|n| !n.is_empty());
+        if let Some(amount) = rate_column.and_then(|r| row.get(r)) {
+            entry.rate = crate::parse_rate(&amount.to_string())
+                .with_context(|| format!("Unable to read the rate in row {}", i + 1))?;
+        }
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+fn cell_duration(cell: &Data, format: DurationFormat) -> Result<Duration> {
+    match (cell, format) {
+        // Times and durations are stored as fractions of a day
+        (Data::DateTime(time), _) => Ok(Duration::milliseconds(
+            (time.as_f64() * 86_400_000.0).round() as i64,
+        )),
+        (Data::String(text), format) => format.parse(text),
+        (Data::Float(hours), DurationFormat::Auto | DurationFormat::Hours) => {
+            WrittenDuration::Hours(*hours).to_duration()
+        }
+        (Data::Int(hours), DurationFormat::Auto | DurationFormat::Hours) => {
+            WrittenDuration::Hours(*hours as f64).to_duration()
+        }
+        (other, _) => anyhow::bail!(
+            "\"{}\" isn't a duration, format the cell as a time or write it as HH:MM:SS",
+            other
+        ),
+    }
+}
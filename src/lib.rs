@@ -1,123 +1,3507 @@
 use anyhow::{Context, Result};
-use chrono::Duration;
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime};
 use clap::Parser;
+use config::{
+    ClientConfig, Config, FixedItem, MinimumCharge, PriceTier, ProjectConfig, ScheduledRate,
+    Shortfall,
+};
 use csv::Reader;
+use events::EventFormat;
+use import::activitywatch::ActivityWatchArgs;
+use import::caldav::CaldavArgs;
+use import::fixed::FixedWidth;
+use import::git::GitArgs;
+use import::google::GoogleArgs;
+use import::ics::IcsArgs;
+use import::jira::JiraArgs;
+use import::sqlite::SqliteArgs;
+use import::Source;
+use render::RenderArgs;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+pub mod checks;
+pub mod config;
+pub mod doctor;
+pub mod events;
+pub mod holidays;
+#[cfg(feature = "http")]
+mod http;
+pub mod import;
+pub mod money;
+pub mod render;
+pub mod sequence;
+
+pub use import::{BadRow, ImportReport, TimeEntry};
+pub use money::{Currency, ExchangeRate, Money};
+pub use render::{render, Format, RenderOptions};
+
 #[cfg(test)]
 mod tests;
 
-/// Generates an invoice from a CSV file
-#[derive(Parser, Debug)]
-#[command(author, version, about)]
-#[command(
-    help_template = "{about-section}\nAuthor: {author-with-newline}Version: {version}\n\n{usage-heading}\n{usage}\n\n{all-args}"
-)]
-pub struct Args {
-    /// The pay rate for the invoice
-    #[arg(short, long)]
-    pub pay_rate: f64,
+/// Generates an invoice from a CSV file
+#[derive(Parser, Debug, Clone, Default)]
+#[command(author, version, about)]
+#[command(
+    help_template = "{about-section}\nAuthor: {author-with-newline}Version: {version}\n\n{usage-heading}\n{usage}\n\n{all-args}"
+)]
+pub struct Args {
+    /// The pay rate for the invoice
+    #[arg(
+        short,
+        long,
+        required_unless_present_any = ["doctor", "validate", "client", "day_rate"]
+    )]
+    pub pay_rate: Option<f64>,
+
+    /// The GST rate for the invoice, as a percentage or a fraction: 5, 5% and 0.05 all mean 5%
+    #[arg(short, long, value_name = "RATE")]
+    pub gst: Option<GstRate>,
+
+    /// A named tax charged on the subtotal as NAME=PERCENT, e.g. "PST=7", repeated for each tax after any --gst. A trailing + charges it on the taxes before it too
+    #[arg(long, value_name = "NAME=PERCENT", value_parser = parse_tax)]
+    pub tax: Vec<Tax>,
+
+    /// The registration number printed with a tax, as NAME=NUMBER
+    #[arg(long, value_name = "NAME=NUMBER", value_parser = parse_alias)]
+    pub tax_number: Vec<(String, String)>,
+
+    /// A fixed-price item charged besides the hours as NAME=AMOUNT, e.g. "Website deployment=500", can be given more than once
+    #[arg(long, value_name = "NAME=AMOUNT", value_parser = parse_item)]
+    pub item: Vec<FixedItem>,
+
+    /// The percentage of the subtotal the client withholds as income tax, e.g. 15, taken off after the taxes
+    #[arg(long, value_name = "PERCENT")]
+    pub withholding: Option<f64>,
+
+    /// Bill by the day at this rate instead of by the hour, counting each day with time logged as a half or full day
+    #[arg(long, value_name = "RATE")]
+    pub day_rate: Option<f64>,
+
+    /// The hours logged on a day that make it a full day with --day-rate
+    #[arg(
+        long,
+        value_name = "HOURS",
+        default_value_t = 4.0,
+        requires = "day_rate"
+    )]
+    pub full_day_hours: f64,
+
+    /// The hours logged on a day that make it a half day with --day-rate, with days under it not billed. Give it the full day's hours to only bill whole days
+    #[arg(
+        long,
+        value_name = "HOURS",
+        default_value_t = 1.0,
+        requires = "day_rate"
+    )]
+    pub half_day_hours: f64,
+
+    /// Bill the hours worked past this many in a day or week at the overtime multiplier, e.g. 40
+    #[arg(long, value_name = "HOURS")]
+    pub overtime_after: Option<f64>,
+
+    /// Whether --overtime-after counts the hours worked each day or each week
+    #[arg(long, value_enum, default_value_t, requires = "overtime_after")]
+    pub overtime_per: OvertimePeriod,
+
+    /// What overtime is charged at, as a multiple of its usual rate
+    #[arg(
+        long,
+        value_name = "MULTIPLIER",
+        default_value_t = 1.5,
+        requires = "overtime_after"
+    )]
+    pub overtime_multiplier: f64,
+
+    /// Whether tax is rounded once on the subtotal or on each line, as jurisdictions differ
+    #[arg(long, value_enum, default_value_t)]
+    pub tax_rounding: TaxRounding,
+
+    /// Your business name, printed at the top of the invoice (defaults to name in [supplier] in the config file)
+    #[arg(long, value_name = "NAME")]
+    pub supplier_name: Option<String>,
+
+    /// A line of your business address, given once per line (defaults to address in [supplier])
+    #[arg(long, value_name = "LINE")]
+    pub supplier_address: Vec<String>,
+
+    /// The name of the client the invoice is billed to (defaults to name in [client.<NAME>.details])
+    #[arg(long, value_name = "NAME")]
+    pub bill_to_name: Option<String>,
+
+    /// A line of the client's address, given once per line (defaults to address in [client.<NAME>.details])
+    #[arg(long, value_name = "LINE")]
+    pub bill_to_address: Vec<String>,
+
+    /// Charge no VAT on an intra-EU business sale, noting that the client accounts for it instead
+    #[arg(long, requires_all = ["vat_id", "client_vat_id"])]
+    pub reverse_charge: bool,
+
+    /// Your VAT identification number, e.g. MT12345678
+    #[arg(long, value_name = "ID")]
+    pub vat_id: Option<String>,
+
+    /// The client's VAT identification number
+    #[arg(long, value_name = "ID")]
+    pub client_vat_id: Option<String>,
+
+    /// What hours worked on a Saturday or Sunday are charged at, as a multiple of their rate, e.g. 1.5
+    #[arg(long, value_name = "MULTIPLIER")]
+    pub weekend_multiplier: Option<f64>,
+
+    /// What hours worked on a public holiday are charged at, as a multiple of their rate, with the holidays read from [holidays] in the config file
+    #[arg(long, value_name = "MULTIPLIER")]
+    pub holiday_multiplier: Option<f64>,
+
+    /// Your internal cost per hour, used to report the invoice's margin
+    #[arg(long)]
+    pub cost_rate: Option<f64>,
+
+    /// The ISO 4217 currency the rates and invoice are in, e.g. EUR (defaults to unnamed dollars)
+    #[arg(long, value_name = "CODE")]
+    pub currency: Option<Currency>,
+
+    /// Adds the totals in the client's currency, at the ECB's exchange rate unless --rate gives one
+    #[arg(long, value_name = "CODE")]
+    pub convert_to: Option<Currency>,
+
+    /// How many of --convert-to's currency one of the invoice's buys
+    #[arg(long, alias = "rate", requires = "convert_to")]
+    pub exchange_rate: Option<f64>,
+
+    /// The day to look up the ECB's exchange rate for (defaults to the latest)
+    #[arg(
+        long,
+        value_name = "YYYY-MM-DD",
+        requires = "convert_to",
+        conflicts_with = "exchange_rate"
+    )]
+    pub rate_date: Option<NaiveDate>,
+
+    /// The time-tracking file or http(s) URL to read from (CSV, or spreadsheet, JSON, TOML/YAML, org-mode, timeclock and iCalendar files by extension)
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        required_unless_present_any = ["source", "doctor"]
+    )]
+    pub file: Option<PathBuf>,
+
+    /// A bearer token sent when --file is an http(s) URL, for private report links
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        env = "PINT_RS_FILE_TOKEN",
+        hide_env_values = true
+    )]
+    pub file_token: Option<String>,
+
+    /// Fetch time entries from a service instead of reading a file
+    #[arg(long, value_enum, conflicts_with = "file")]
+    pub source: Option<Source>,
+
+    /// The first day to fetch entries for, or to invoice a file's entries from (defaults to the start of the month)
+    #[arg(long, value_name = "YYYY-MM-DD")]
+    pub from: Option<NaiveDate>,
+
+    /// The last day to fetch entries for, after which a file's entries are warned about (defaults to the end of the month)
+    #[arg(long, value_name = "YYYY-MM-DD")]
+    pub to: Option<NaiveDate>,
+
+    /// Invoice one calendar month, leaving out a file's entries from other months
+    #[arg(long, visible_alias = "period", value_name = "YYYY-MM", value_parser = parse_month, conflicts_with_all = ["from", "to"])]
+    pub month: Option<NaiveDate>,
+
+    /// Invoice the calendar month before this one
+    #[arg(long, conflicts_with_all = ["from", "to", "month"])]
+    pub last_month: bool,
+
+    /// The column of the day each entry was tracked (defaults to one headed "Date", if any)
+    #[arg(long, value_name = "COLUMN")]
+    pub date_column: Option<Column>,
+
+    /// Bill a project under another name, e.g. "Acme Webiste=Acme Website", can be given more than once
+    #[arg(long, value_name = "FROM=TO", value_parser = parse_alias)]
+    pub alias: Vec<(String, String)>,
+
+    /// What to do with single entries longer than 24 hours, usually a timer left running
+    #[arg(long, value_enum, default_value_t)]
+    pub long_entry: LongEntryPolicy,
+
+    /// Remove entries that repeat another's project, start time and duration, instead of only warning about them
+    #[arg(long)]
+    pub dedupe: bool,
+
+    /// Take the time entries overlap by off the later of them, instead of only warning about it
+    #[arg(long)]
+    pub trim_overlaps: bool,
+
+    /// Warn about single entries longer than this, which are usually a timer left running, or 0 to not check
+    #[arg(long, value_name = "HOURS", default_value = "16")]
+    pub max_entry_hours: Option<f64>,
+
+    /// Refuse to build the invoice when an entry or a day's entries are implausibly long, instead of warning
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Skip CSV rows that can't be read, warning about each, instead of stopping at the first
+    #[arg(long)]
+    pub lenient: bool,
+
+    /// What to do when an import has more than one entry for a project
+    #[arg(long, value_enum, default_value_t)]
+    pub merge: MergePolicy,
+
+    /// Round billed time up to a multiple of this, e.g. 15m or 0.1
+    #[arg(long, value_name = "DURATION", value_parser = parse_increment)]
+    pub round_increment: Option<Duration>,
+
+    /// Whether --round-increment rounds each entry or each project's total
+    #[arg(long, value_enum, default_value_t, requires = "round_increment")]
+    pub round_per: RoundingScope,
+
+    /// Treat every imported entry as estimated, such as a timesheet reconstructed after the fact
+    #[arg(long)]
+    pub estimated: bool,
+
+    /// The share of estimated time that's billed, e.g. 0.9 for 90% (defaults to all of it)
+    #[arg(long, value_name = "FRACTION", value_parser = parse_share)]
+    pub estimate_share: Option<f64>,
+
+    /// Print an evenly spread sample of this many parsed entries before the invoice
+    #[arg(long, value_name = "COUNT")]
+    pub preview_entries: Option<usize>,
+
+    /// Leave out entries dated in the future or after --to, instead of only warning about them
+    #[arg(long)]
+    pub no_future: bool,
+
+    /// The client the invoice is for, whose settings and default pay rate, GST and currency are read from [client.<NAME>] in the config file
+    #[arg(long, value_name = "NAME")]
+    pub client: Option<String>,
+
+    /// Give the invoice the client's next number, counted in the sequence file and only used up once the invoice is written
+    #[arg(long)]
+    pub number: bool,
+
+    /// How invoice numbers are written, with {client}, {year} and {seq}, padded with e.g. {seq:3} (defaults to number_format in [client.<NAME>], or else "{year}-{seq:3}")
+    #[arg(long, value_name = "FORMAT", requires = "number")]
+    pub number_format: Option<String>,
+
+    /// The file the last invoice number issued to each client is kept in (defaults to ~/.local/share/pint-rs/sequence.json)
+    #[arg(long, value_name = "PATH", requires = "number")]
+    pub sequence_file: Option<PathBuf>,
+
+    /// The day the invoice is issued on (defaults to today)
+    #[arg(long, value_name = "YYYY-MM-DD")]
+    pub invoice_date: Option<NaiveDate>,
+
+    /// The number of days after the invoice date that payment is due, e.g. 30 or net30
+    #[arg(long, value_name = "DAYS", value_parser = parse_terms)]
+    pub terms: Option<u32>,
+
+    /// The locale dates are written in, e.g. fr_FR (defaults to en_US)
+    #[arg(long, value_parser = parse_locale)]
+    pub locale: Option<chrono::Locale>,
+
+    /// The column holding each entry's project, by zero-based index or header name (defaults to one headed "Project", or else 0)
+    #[arg(long, value_name = "COLUMN")]
+    pub project_column: Option<Column>,
+
+    /// The column holding each entry's duration, by zero-based index or header name (defaults to one headed "Duration", or else 3)
+    #[arg(long, value_name = "COLUMN")]
+    pub duration_column: Option<Column>,
+
+    /// The column saying whether each entry is billable, e.g. Yes/No (defaults to one headed "Billable", if any)
+    #[arg(long, value_name = "COLUMN")]
+    pub billable_column: Option<Column>,
+
+    /// The column saying whether each entry is tax exempt, e.g. Yes/No (defaults to one headed "Tax Exempt", if any)
+    #[arg(long, value_name = "COLUMN")]
+    pub tax_exempt_column: Option<Column>,
+
+    /// The column listing each entry's tags, separated by commas (defaults to one headed "Tags", if any)
+    #[arg(long, value_name = "COLUMN")]
+    pub tags_column: Option<Column>,
+
+    /// Only bill this project, or the projects a regex matches, can be given more than once
+    #[arg(long = "project", value_name = "NAME|REGEX")]
+    pub included_projects: Vec<ProjectPattern>,
+
+    /// Leave out this project, or the projects a regex matches, can be given more than once
+    #[arg(long = "exclude-project", value_name = "NAME|REGEX")]
+    pub excluded_projects: Vec<ProjectPattern>,
+
+    /// Only bill entries with this tag, can be given more than once to bill entries with any of them
+    #[arg(long, value_name = "TAG")]
+    pub tag: Vec<String>,
+
+    /// Leave out entries with this tag, can be given more than once
+    #[arg(long, value_name = "TAG")]
+    pub exclude_tag: Vec<String>,
+
+    /// The most hours billed for a project as NAME=HOURS, e.g. "ProjectX=40", with the rest listed as courtesy time
+    #[arg(long, value_name = "NAME=HOURS", value_parser = parse_cap)]
+    pub cap: Vec<(String, f64)>,
+
+    /// List the hours left off the invoice as non-billable in a section of their own
+    #[arg(long)]
+    pub list_non_billable: bool,
+
+    /// The column describing each entry (defaults to one headed "Description" or "Notes", if any)
+    #[arg(long, value_name = "COLUMN")]
+    pub notes_column: Option<Column>,
+
+    /// List each entry's date, description and hours under its project
+    #[arg(long)]
+    pub detailed: bool,
+
+    /// The column naming each entry's client (defaults to one headed "Client", if any)
+    #[arg(long, value_name = "COLUMN")]
+    pub client_column: Option<Column>,
+
+    /// The column of each entry's hourly rate, which overrides --pay-rate for it (defaults to one headed "Billable Rate" or "Rate", if any)
+    #[arg(long, value_name = "COLUMN")]
+    pub rate_column: Option<Column>,
+
+    /// Build an invoice per client in the client column, written to invoice-<client>.txt (or .json)
+    #[arg(long, conflicts_with_all = ["client", "events"])]
+    pub split_by_client: bool,
+
+    /// How durations are written, guessed from each one by default
+    #[arg(long, value_enum, default_value_t)]
+    pub duration_format: DurationFormat,
+
+    /// Read --file as fixed-width columns, giving the project and duration byte ranges, e.g. 0-20,40-48
+    #[arg(long, value_name = "RANGES")]
+    pub fixed_width: Option<FixedWidth>,
+
+    /// The column of CSV files holding when each entry started, to work out durations with --end-column
+    #[arg(long, value_name = "COLUMN", requires = "end_column")]
+    pub start_column: Option<Column>,
+
+    /// The column of CSV files holding when each entry ended, to work out durations with --start-column
+    #[arg(long, value_name = "COLUMN", requires = "start_column")]
+    pub end_column: Option<Column>,
+
+    /// The character separating fields in CSV files, e.g. ';' for many European exports (guessed when not given)
+    #[arg(long, value_name = "CHAR", value_parser = parse_delimiter)]
+    pub delimiter: Option<u8>,
+
+    /// Read CSV files as tab-separated
+    #[arg(long, conflicts_with = "delimiter")]
+    pub tab: bool,
+
+    /// Read the first row of CSV files as an entry rather than column headers, which is otherwise guessed
+    #[arg(long)]
+    pub no_headers: bool,
+
+    /// The text encoding of CSV files, e.g. windows-1252 or utf-16 (guessed when not given)
+    #[arg(long, value_name = "LABEL", value_parser = import::parse_encoding)]
+    pub encoding: Option<&'static encoding_rs::Encoding>,
+
+    /// The sheet to read from spreadsheet files (defaults to the first)
+    #[arg(long, value_name = "NAME")]
+    pub sheet: Option<String>,
+
+    /// The format to print the invoice in
+    #[arg(long, value_enum, default_value_t)]
+    pub format: Format,
+
+    /// Write progress events to stdout for wrapper programs, with the invoice inside the last one
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub events: Option<EventFormat>,
+
+    /// Check the config file and reach the services it sets up, instead of making an invoice
+    #[arg(long)]
+    pub doctor: bool,
+
+    /// Read the entries and report the hours per project and any problems, instead of making an invoice
+    #[arg(long, conflicts_with = "split_by_client")]
+    pub validate: bool,
+
+    /// The config file to read (defaults to ~/.config/pint-rs/config.toml)
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub activitywatch: ActivityWatchArgs,
+
+    #[command(flatten)]
+    pub caldav: CaldavArgs,
+
+    #[command(flatten)]
+    pub git: GitArgs,
+
+    #[command(flatten)]
+    pub google: GoogleArgs,
+
+    #[command(flatten)]
+    pub ics: IcsArgs,
+
+    #[command(flatten)]
+    pub sqlite: SqliteArgs,
+
+    #[command(flatten)]
+    pub render: RenderArgs,
+
+    #[command(flatten)]
+    pub jira: JiraArgs,
+}
+
+/// How to treat a single time entry longer than a day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LongEntryPolicy {
+    /// Bill the entry as it is, with a warning
+    #[default]
+    Keep,
+    /// Bill 24 hours of the entry
+    Clamp,
+    /// Split the entry into one entry per day it covers
+    Split,
+    /// Refuse to build the invoice
+    Error,
+}
+
+/// How to treat a project that appears in more than one entry of an import
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MergePolicy {
+    /// Bill the time of every entry
+    #[default]
+    Sum,
+    /// Bill only the project's longest entry
+    Max,
+    /// Bill only the project's last entry
+    Last,
+    /// Refuse to build the invoice, for inputs that should already be totalled per project
+    Error,
+}
+
+impl MergePolicy {
+    /// Keeps the entries the policy bills, in the order their projects first appear
+    pub fn merge(self, entries: Vec<TimeEntry>) -> Result<Vec<TimeEntry>> {
+        if self == MergePolicy::Sum {
+            return Ok(entries);
+        }
+
+        let mut merged: Vec<TimeEntry> = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let kept = merged.iter().position(|e| e.project == entry.project);
+            match (self, kept) {
+                (MergePolicy::Sum, _) | (_, None) => merged.push(entry),
+                (MergePolicy::Max, Some(i)) if entry.duration > merged[i].duration => {
+                    merged[i] = entry
+                }
+                (MergePolicy::Max, Some(_)) => {}
+                (MergePolicy::Last, Some(i)) => merged[i] = entry,
+                (MergePolicy::Error, Some(_)) => anyhow::bail!(
+                    "\"{}\" has more than one entry, which --merge error doesn't allow",
+                    entry.project
+                ),
+            }
+        }
+
+        Ok(merged)
+    }
+}
+
+/// What billed time is rounded up per, when it's rounded to an increment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum RoundingScope {
+    /// Round each entry up on its own
+    #[default]
+    Entry,
+    /// Round each project's total up, once for each rate it's billed at
+    Project,
+}
+
+/// Whether tax is worked out on each line of the invoice or once on its subtotal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaxRounding {
+    /// Work tax out on the subtotal and round it once
+    #[default]
+    Total,
+    /// Work tax out on each line, rounding it there, and add those up
+    Line,
+}
+
+/// The stretch of time overtime hours are counted over
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OvertimePeriod {
+    Day,
+    /// A Monday to Sunday week
+    #[default]
+    Week,
+}
+
+impl OvertimePeriod {
+    /// The day, or the year and number of the ISO week, a date is in
+    fn of(&self, date: NaiveDate) -> (i32, u32) {
+        match self {
+            OvertimePeriod::Day => (date.year(), date.ordinal()),
+            OvertimePeriod::Week => (date.iso_week().year(), date.iso_week().week()),
+        }
+    }
+}
+
+/// When time is charged at a premium, such as 1.5 times the rate after 40
+/// hours a week
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OvertimeRule {
+    after: Duration,
+    per: OvertimePeriod,
+    multiplier: f64,
+}
+
+/// How days are counted when billing by the day
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DayRateRule {
+    rate: Money,
+    full_day: Duration,
+    half_day: Duration,
+}
+
+/// Rounds billed time up to a whole number of increments, like the six or
+/// fifteen minutes legal and consulting work is usually billed in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rounding {
+    pub increment: Duration,
+    pub scope: RoundingScope,
+}
+
+impl Rounding {
+    /// Rounds a duration up, leaving corrections as they are
+    pub fn round_up(&self, duration: Duration) -> Duration {
+        let increment = self.increment.num_seconds();
+        let seconds = duration.num_seconds();
+        match seconds > 0 && increment > 0 {
+            true => Duration::seconds((seconds + increment - 1) / increment * increment),
+            false => duration,
+        }
+    }
+}
+
+fn parse_increment(str: &str) -> Result<Duration> {
+    let increment = DurationFormat::Auto.parse(str)?;
+    if increment <= Duration::zero() {
+        anyhow::bail!("The increment \"{}\" should be longer than nothing", str);
+    }
+
+    Ok(increment)
+}
+
+/// A column in a CSV file or spreadsheet, by its zero-based index or its header
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    Index(usize),
+    Header(String),
+    /// The nth column with a header that's repeated, counting from 1, written `Duration[2]`
+    Repeated(String, usize),
+    /// The first column with one of these headers, or the index when none has
+    Guess(&'static [&'static str], usize),
+}
+
+/// Headers that exports commonly give the project column
+const PROJECT_HEADERS: &[&str] = &["Project", "Project Name", "Project Title", "Job"];
+
+/// Headers that exports commonly give the column saying whether an entry is charged for
+const BILLABLE_HEADERS: &[&str] = &["Billable", "Billable?", "Is Billable"];
+
+/// Headers that exports commonly give the column saying whether an entry is taxed
+const TAX_EXEMPT_HEADERS: &[&str] = &["Tax Exempt", "Tax Exempt?", "Exempt"];
+
+/// Headers that exports commonly give the column of the day each entry was tracked
+const DATE_HEADERS: &[&str] = &["Date", "Start Date", "Day"];
+
+/// Headers that exports commonly give the column of each entry's client
+const CLIENT_HEADERS: &[&str] = &["Client", "Customer", "Client Name"];
+
+/// Headers that exports commonly give the column of each entry's hourly rate
+const RATE_HEADERS: &[&str] = &["Billable Rate", "Hourly Rate", "Rate"];
+
+/// Headers that exports commonly give the column describing each entry
+const NOTES_HEADERS: &[&str] = &["Description", "Notes", "Note", "Task"];
+
+/// Headers that exports commonly give the column of each entry's tags
+const TAGS_HEADERS: &[&str] = &["Tags", "Tag", "Labels"];
+
+/// Headers that exports commonly give the column of `HH:MM:SS` durations
+const DURATION_HEADERS: &[&str] = &["Duration", "Time Spent", "Elapsed", "Elapsed Time"];
+
+impl std::str::FromStr for Column {
+    type Err = std::convert::Infallible;
+
+    fn from_str(str: &str) -> std::result::Result<Self, Self::Err> {
+        let str = str.trim();
+        if let Ok(index) = str.parse() {
+            return Ok(Column::Index(index));
+        }
+
+        let repeated = str
+            .strip_suffix(']')
+            .and_then(|s| s.rsplit_once('['))
+            .and_then(|(name, n)| Some((name.trim(), n.parse::<usize>().ok()?)))
+            .filter(|(_, n)| *n > 0);
+        Ok(match repeated {
+            Some((name, n)) => Column::Repeated(name.to_owned(), n),
+            None => Column::Header(str.to_owned()),
+        })
+    }
+}
+
+impl Column {
+    /// Finds the column's index in a header row, comparing names case-insensitively
+    ///
+    /// A header that's repeated has to be picked by its position among the
+    /// repeats, rather than quietly read from the first of them.
+    pub fn position<S: AsRef<str>>(&self, headers: &[S]) -> Result<usize> {
+        match self {
+            Column::Index(index) => Ok(*index),
+            Column::Header(name) => match Self::matching(headers, name)[..] {
+                [] => anyhow::bail!("No column has the header \"{}\"", name),
+                [index] => Ok(index),
+                ref indices => anyhow::bail!(
+                    "{} columns have the header \"{}\", pick one with \"{}[1]\" to \"{}[{}]\"",
+                    indices.len(),
+                    name,
+                    name,
+                    name,
+                    indices.len()
+                ),
+            },
+            Column::Repeated(name, n) => {
+                let indices = Self::matching(headers, name);
+                indices.get(n - 1).copied().with_context(|| {
+                    format!(
+                        "Only {} columns have the header \"{}\", so there's no \"{}[{}]\"",
+                        indices.len(),
+                        name,
+                        name,
+                        n
+                    )
+                })
+            }
+            Column::Guess(names, index) => {
+                for name in names.iter() {
+                    if !Self::matching(headers, name).is_empty() {
+                        return Column::Header(name.to_string()).position(headers);
+                    }
+                }
+                Ok(*index)
+            }
+        }
+    }
+
+    fn matching<S: AsRef<str>>(headers: &[S], name: &str) -> Vec<usize> {
+        headers
+            .iter()
+            .enumerate()
+            .filter(|(_, h)| h.as_ref().trim().eq_ignore_ascii_case(name))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Where the project and duration of each entry are in a CSV file or spreadsheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Columns {
+    pub project: Column,
+    pub duration: Column,
+    /// Start and end timestamps to work each duration out from, instead of `duration`
+    pub span: Option<(Column, Column)>,
+    /// How the durations in the `duration` column are written
+    pub duration_format: DurationFormat,
+    /// The column saying whether each entry is charged for
+    pub billable: Option<Column>,
+    /// The column saying whether each entry is left out of what's taxed
+    pub tax_exempt: Option<Column>,
+    /// The column listing each entry's tags
+    pub tags: Option<Column>,
+    /// The column describing the work behind each entry
+    pub notes: Option<Column>,
+    /// The column naming each entry's client
+    pub client: Option<Column>,
+    /// The column of the day each entry was tracked
+    pub date: Option<Column>,
+    /// The column of each entry's hourly rate
+    pub rate: Option<Column>,
+}
+
+impl Columns {
+    /// Finds the billable column, by default the one with a usual header if
+    /// there is one, since most files don't have it
+    pub(crate) fn billable_position<S: AsRef<str>>(&self, headers: &[S]) -> Result<Option<usize>> {
+        Self::optional_position(&self.billable, BILLABLE_HEADERS, headers)
+    }
+
+    /// Finds the tax exempt column, like the billable column
+    pub(crate) fn tax_exempt_position<S: AsRef<str>>(
+        &self,
+        headers: &[S],
+    ) -> Result<Option<usize>> {
+        Self::optional_position(&self.tax_exempt, TAX_EXEMPT_HEADERS, headers)
+    }
+
+    /// Finds the tags column, like the billable column
+    pub(crate) fn tags_position<S: AsRef<str>>(&self, headers: &[S]) -> Result<Option<usize>> {
+        Self::optional_position(&self.tags, TAGS_HEADERS, headers)
+    }
+
+    /// Finds the description column, like the billable column
+    pub(crate) fn notes_position<S: AsRef<str>>(&self, headers: &[S]) -> Result<Option<usize>> {
+        Self::optional_position(&self.notes, NOTES_HEADERS, headers)
+    }
+
+    /// Finds the client column, like the billable column
+    pub(crate) fn client_position<S: AsRef<str>>(&self, headers: &[S]) -> Result<Option<usize>> {
+        Self::optional_position(&self.client, CLIENT_HEADERS, headers)
+    }
+
+    /// Finds the date column, like the billable column
+    pub(crate) fn date_position<S: AsRef<str>>(&self, headers: &[S]) -> Result<Option<usize>> {
+        Self::optional_position(&self.date, DATE_HEADERS, headers)
+    }
+
+    /// Finds the rate column, like the billable column
+    pub(crate) fn rate_position<S: AsRef<str>>(&self, headers: &[S]) -> Result<Option<usize>> {
+        Self::optional_position(&self.rate, RATE_HEADERS, headers)
+    }
+
+    fn optional_position<S: AsRef<str>>(
+        column: &Option<Column>,
+        names: &[&str],
+        headers: &[S],
+    ) -> Result<Option<usize>> {
+        match column {
+            Some(column) => column.position(headers).map(Some),
+            None => Ok(names
+                .iter()
+                .find_map(|name| Column::Header(name.to_string()).position(headers).ok())),
+        }
+    }
+}
+
+/// Splits a cell of tags like "clientA, urgent" into the tags
+pub(crate) fn parse_tags(str: &str) -> Vec<String> {
+    str.split([',', ';'])
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Reads a billable flag such as Yes/No or true/false, where an empty cell is billable
+pub(crate) fn parse_billable(str: &str) -> Result<bool> {
+    match str.trim().to_ascii_lowercase().as_str() {
+        "" | "yes" | "y" | "true" | "1" | "billable" => Ok(true),
+        "no" | "n" | "false" | "0" | "non-billable" => Ok(false),
+        other => anyhow::bail!("\"{}\" should be Yes or No", other),
+    }
+}
+
+/// Reads whether an entry is tax exempt, where an empty cell means it's taxed
+pub(crate) fn parse_exempt(str: &str) -> Result<bool> {
+    match str.trim().to_ascii_lowercase().as_str() {
+        "yes" | "y" | "true" | "1" | "exempt" => Ok(true),
+        "" | "no" | "n" | "false" | "0" => Ok(false),
+        other => anyhow::bail!("\"{}\" should be Yes or No", other),
+    }
+}
+
+/// Reads an hourly rate such as "$110.00" or "1,250", where an empty cell has none
+pub(crate) fn parse_rate(str: &str) -> Result<Option<f64>> {
+    let symbol = |c: char| !c.is_alphanumeric() && !matches!(c, '.' | '-');
+    let amount = str
+        .trim_matches(|c: char| c.is_whitespace() || symbol(c))
+        .replace(',', "");
+    if amount.is_empty() {
+        return Ok(None);
+    }
+
+    let rate: f64 = amount
+        .parse()
+        .with_context(|| format!("Unable to parse rate \"{}\"", str.trim()))?;
+    if rate < 0.0 {
+        anyhow::bail!("Rate \"{}\" is negative", str.trim());
+    }
+
+    Ok(Some(rate))
+}
+
+/// How durations are written in a CSV file or spreadsheet
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DurationFormat {
+    /// Decimal hours when they're only a number, and clock durations otherwise
+    #[default]
+    Auto,
+    /// HH:MM:SS, or written out like 1h 30m
+    Clock,
+    /// Decimal hours such as 1.75, or 0,5 with a decimal comma
+    Hours,
+}
+
+impl DurationFormat {
+    /// Parses a duration, negative for a correction when it has a leading minus
+    pub fn parse(self, str: &str) -> Result<Duration> {
+        let str = str.trim();
+        if let Some(correction) = str.strip_prefix('-') {
+            return Ok(-self.parse(correction)?);
+        }
+        match self {
+            DurationFormat::Auto if str.contains(|c: char| c == ':' || c.is_ascii_alphabetic()) => {
+                InvoiceBuilder::parse_duration_str(str)
+            }
+            DurationFormat::Clock => InvoiceBuilder::parse_duration_str(str),
+            DurationFormat::Auto | DurationFormat::Hours => {
+                let hours: f64 = str
+                    .replace(',', ".")
+                    .parse()
+                    .with_context(|| format!("\"{}\" isn't a number of hours", str))?;
+                import::WrittenDuration::Hours(hours).to_duration()
+            }
+        }
+    }
+}
+
+impl Default for Columns {
+    /// The project and duration columns by their usual headers, or where the
+    /// Toggl CSV export puts them
+    fn default() -> Self {
+        Self {
+            project: Column::Guess(PROJECT_HEADERS, 0),
+            duration: Column::Guess(DURATION_HEADERS, 3),
+            span: None,
+            duration_format: DurationFormat::Auto,
+            billable: None,
+            tax_exempt: None,
+            tags: None,
+            notes: None,
+            client: None,
+            date: None,
+            rate: None,
+        }
+    }
+}
+
+/// How the fields of a CSV file are laid out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CsvFormat {
+    pub delimiter: u8,
+    /// Whether the first row names the columns
+    pub headers: bool,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            headers: true,
+        }
+    }
+}
+
+/// Delimiters [`CsvFormat::sniff`] chooses between, in order of preference
+const CSV_DELIMITERS: [u8; 4] = [b',', b';', b'\t', b'|'];
+
+/// How much of a CSV file [`CsvFormat::sniff`] looks at
+const CSV_SNIFF_BYTES: usize = 1024;
+
+impl CsvFormat {
+    /// Works out whatever of the layout wasn't given from the start of the file
+    ///
+    /// The delimiter is the candidate found the same number of times on every
+    /// line, preferring the most frequent, and there's a header row unless the
+    /// first row already holds an `HH:MM:SS` duration.
+    pub fn sniff(contents: &[u8], delimiter: Option<u8>, headers: Option<bool>) -> Self {
+        let sample = &contents[..contents.len().min(CSV_SNIFF_BYTES)];
+        let mut lines: Vec<&[u8]> = sample
+            .split(|&b| b == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .filter(|line| !line.is_empty())
+            .collect();
+        // The last line is likely cut short
+        if contents.len() > sample.len() && lines.len() > 1 {
+            lines.pop();
+        }
+
+        let delimiter = delimiter.unwrap_or_else(|| {
+            CSV_DELIMITERS
+                .iter()
+                .filter_map(|&candidate| {
+                    let counts: Vec<usize> = lines
+                        .iter()
+                        .map(|line| Self::count_unquoted(line, candidate))
+                        .collect();
+                    let first = *counts.first()?;
+                    (first > 0 && counts.iter().all(|&c| c == first)).then_some((candidate, first))
+                })
+                .fold(
+                    None,
+                    |best: Option<(u8, usize)>, (candidate, count)| match best {
+                        Some((_, most)) if most >= count => best,
+                        _ => Some((candidate, count)),
+                    },
+                )
+                .map_or(b',', |(candidate, _)| candidate)
+        });
+
+        let headers = headers.unwrap_or_else(|| {
+            let Some(first) = lines.first() else {
+                return true;
+            };
+            !String::from_utf8_lossy(first)
+                .split(delimiter as char)
+                .any(|field| Self::looks_like_clock(field.trim().trim_matches('"')))
+        });
+
+        Self { delimiter, headers }
+    }
+
+    fn count_unquoted(line: &[u8], delimiter: u8) -> usize {
+        let mut quoted = false;
+        line.iter()
+            .filter(|&&b| {
+                quoted ^= b == b'"';
+                b == delimiter && !quoted
+            })
+            .count()
+    }
+
+    fn looks_like_clock(field: &str) -> bool {
+        let parts: Vec<&str> = field.split(':').collect();
+        parts.len() == 3
+            && parts
+                .iter()
+                .all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+    }
+
+    pub fn reader<'a>(&self, contents: &'a [u8]) -> Reader<&'a [u8]> {
+        csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .has_headers(self.headers)
+            .from_reader(contents)
+    }
+}
+
+/// A project name, or a regex that has to match the whole of a project's
+/// name, compared without regard to case
+#[derive(Debug, Clone)]
+pub struct ProjectPattern(regex::Regex);
+
+impl ProjectPattern {
+    pub fn matches(&self, project: &str) -> bool {
+        self.0.is_match(project)
+    }
+}
+
+impl PartialEq for ProjectPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl std::str::FromStr for ProjectPattern {
+    type Err = anyhow::Error;
+
+    fn from_str(str: &str) -> Result<Self> {
+        regex::RegexBuilder::new(&format!("^(?:{})$", str.trim()))
+            .case_insensitive(true)
+            .build()
+            .map(Self)
+            .with_context(|| format!("Invalid project pattern \"{}\"", str))
+    }
+}
+
+/// Replaces each `@FILE` argument with the arguments written in the file, so
+/// a long invocation can be kept per client and reused
+pub fn expand_response_files<I: IntoIterator<Item = std::ffi::OsString>>(
+    args: I,
+) -> Result<Vec<std::ffi::OsString>> {
+    let mut expanded = Vec::new();
+    for (i, arg) in args.into_iter().enumerate() {
+        let file = arg
+            .to_str()
+            .and_then(|a| a.strip_prefix('@'))
+            .filter(|_| i > 0);
+        let Some(file) = file else {
+            expanded.push(arg);
+            continue;
+        };
+
+        let contents = std::fs::read_to_string(file)
+            .with_context(|| format!("Unable to read the arguments in \"{}\"", file))?;
+        let words = parse_response_file(&contents)
+            .with_context(|| format!("Unable to read the arguments in \"{}\"", file))?;
+        expanded.extend(words.into_iter().map(Into::into));
+    }
+
+    Ok(expanded)
+}
+
+/// Splits a response file into arguments at whitespace, keeping quoted text
+/// together and leaving out lines starting with `#`
+pub(crate) fn parse_response_file(contents: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let mut word: Option<String> = None;
+        let mut quote = None;
+        for c in line.chars() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+                (None, '"' | '\'') => {
+                    quote = Some(c);
+                    word.get_or_insert_with(String::new);
+                }
+                (None, c) if c.is_whitespace() => words.extend(word.take()),
+                (None, c) => word.get_or_insert_with(String::new).push(c),
+            }
+        }
+        if quote.is_some() {
+            anyhow::bail!("Line {} has a quote that isn't closed", number + 1);
+        }
+        words.extend(word);
+    }
+
+    Ok(words)
+}
+
+/// The error for a capability left out of this build, saying how to get it
+#[cfg_attr(
+    all(
+        feature = "http",
+        feature = "jws",
+        feature = "sqlite",
+        feature = "xlsx"
+    ),
+    allow(dead_code)
+)]
+pub(crate) fn missing_feature(capability: &str, feature: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{} requires pint-rs to be built with the `{}` feature, which `cargo install pint-rs --features {}` does",
+        capability,
+        feature,
+        feature
+    )
+}
+
+impl Args {
+    /// The arguments with the pay rate, GST and currency that weren't given
+    /// filled in from a client's config section
+    pub fn with_client_defaults(&self, name: &str, client: &ClientConfig) -> Result<Args> {
+        let pay_rate = self.pay_rate.or(client.pay_rate);
+        if pay_rate.is_none() && self.day_rate.is_none() && !self.validate {
+            anyhow::bail!(
+                "No pay rate for client {}, give --pay-rate or set pay_rate in [client.{}]",
+                name,
+                name
+            );
+        }
+
+        Ok(Args {
+            pay_rate,
+            gst: self.gst.or(client.gst),
+            currency: self.currency.or(client.currency),
+            ..self.clone()
+        })
+    }
+
+    /// The first and last days asked for, with --month and --last-month
+    /// turned into the days they cover
+    pub fn date_bounds(&self, today: NaiveDate) -> (Option<NaiveDate>, Option<NaiveDate>) {
+        let month = match self.last_month {
+            true => today
+                .with_day(1)
+                .and_then(|d| d.checked_sub_months(chrono::Months::new(1))),
+            false => self.month,
+        };
+
+        match month {
+            Some(month) => {
+                let (from, to) = import::date_range(Some(month), None, today);
+                (Some(from), Some(to))
+            }
+            None => (self.from, self.to),
+        }
+    }
+}
+
+fn parse_month(str: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", str.trim()), "%Y-%m-%d")
+        .with_context(|| format!("\"{}\" isn't a month like 2024-06", str))
+}
+
+fn parse_alias(str: &str) -> Result<(String, String)> {
+    let (from, to) = str
+        .split_once('=')
+        .with_context(|| format!("Alias \"{}\" should look like FROM=TO", str))?;
+
+    Ok((from.trim().to_owned(), to.trim().to_owned()))
+}
+
+/// A GST rate, which is given as a percentage or as a fraction of one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GstRate {
+    pub percent: f64,
+    /// Whether the rate could have been meant the other way, like 0.5 for
+    /// half a percent rather than 50%
+    pub ambiguous: bool,
+}
+
+/// The highest GST anywhere charges, above which a fraction like 0.5 was more
+/// likely meant as half a percent
+const PLAUSIBLE_GST_PERCENT: f64 = 30.0;
+
+impl From<f64> for GstRate {
+    /// Reads numbers up to one as fractions and larger ones as percentages,
+    /// since GST is never more than 100%
+    fn from(rate: f64) -> Self {
+        match rate < 1.0 {
+            true => {
+                // Rounded so 0.07 shows as 7% rather than 7.000000000000001%
+                let percent = (rate * 100.0 * 1e6).round() / 1e6;
+                Self {
+                    percent,
+                    ambiguous: percent > PLAUSIBLE_GST_PERCENT,
+                }
+            }
+            false => Self {
+                percent: rate,
+                ambiguous: rate == 1.0,
+            },
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for GstRate {
+    /// Reads `gst = 0.05` like `--gst 0.05`, and `gst = "5%"` like `--gst 5%`
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Written {
+            Number(f64),
+            Text(String),
+        }
+
+        match Written::deserialize(deserializer)? {
+            Written::Number(rate) if rate < 0.0 => Err(serde::de::Error::custom(format!(
+                "GST of {} can't be negative",
+                rate
+            ))),
+            Written::Number(rate) => Ok(Self::from(rate)),
+            Written::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+impl std::str::FromStr for GstRate {
+    type Err = anyhow::Error;
+
+    fn from_str(str: &str) -> Result<Self> {
+        let (number, percent_sign) = match str.trim().strip_suffix('%') {
+            Some(number) => (number, true),
+            None => (str, false),
+        };
+        let rate: f64 = number
+            .trim()
+            .parse()
+            .with_context(|| format!("GST \"{}\" isn't a number", str))?;
+        if rate < 0.0 {
+            anyhow::bail!("GST of {} can't be negative", str);
+        }
+
+        Ok(match percent_sign {
+            true => Self {
+                percent: rate,
+                ambiguous: false,
+            },
+            false => Self::from(rate),
+        })
+    }
+}
+
+fn parse_tax(str: &str) -> Result<Tax> {
+    let (name, percent) = str
+        .split_once('=')
+        .with_context(|| format!("Tax \"{}\" should look like NAME=PERCENT", str))?;
+    let percent = percent.trim();
+    let (percent, compound) = match percent.strip_suffix('+') {
+        Some(percent) => (percent, true),
+        None => (percent, false),
+    };
+    let percent: f64 = percent
+        .trim()
+        .parse()
+        .with_context(|| format!("The percentage of tax \"{}\" isn't a number", str))?;
+
+    Ok(Tax {
+        name: name.trim().to_owned(),
+        percent,
+        compound,
+        registration: None,
+    })
+}
+
+/// Reads payment terms such as "30", "net30" or "Net 30", or "receipt" for
+/// payment on receipt
+fn parse_terms(str: &str) -> Result<u32> {
+    let terms = str.trim().to_ascii_lowercase();
+    if terms == "receipt" || terms == "due on receipt" {
+        return Ok(0);
+    }
+
+    let days = terms.strip_prefix("net").unwrap_or(&terms);
+    days.trim()
+        .parse()
+        .with_context(|| format!("Terms \"{}\" should look like 30 or net30", str))
+}
+
+fn parse_cap(str: &str) -> Result<(String, f64)> {
+    let (project, hours) = str
+        .rsplit_once('=')
+        .with_context(|| format!("Cap \"{}\" should look like NAME=HOURS", str))?;
+    let hours: f64 = hours
+        .trim()
+        .parse()
+        .with_context(|| format!("The hours of cap \"{}\" aren't a number", str))?;
+    if hours < 0.0 {
+        anyhow::bail!("Cap \"{}\" is negative", str);
+    }
+
+    Ok((project.trim().to_owned(), hours))
+}
+
+fn parse_item(str: &str) -> Result<FixedItem> {
+    let (name, amount) = str
+        .rsplit_once('=')
+        .with_context(|| format!("Item \"{}\" should look like NAME=AMOUNT", str))?;
+    let amount: f64 = amount
+        .trim()
+        .parse()
+        .with_context(|| format!("The amount of item \"{}\" isn't a number", str))?;
+
+    Ok(FixedItem {
+        name: name.trim().to_owned(),
+        amount,
+        tax_exempt: false,
+    })
+}
+
+fn parse_share(str: &str) -> Result<f64> {
+    let share: f64 = str
+        .trim()
+        .parse()
+        .with_context(|| format!("\"{}\" isn't a number", str))?;
+    if !(0.0..=1.0).contains(&share) {
+        anyhow::bail!("{} should be between 0 and 1, e.g. 0.9 for 90%", share);
+    }
+
+    Ok(share)
+}
+
+fn parse_delimiter(str: &str) -> Result<u8> {
+    match str.as_bytes() {
+        [delimiter] => Ok(*delimiter),
+        b"\\t" => Ok(b'\t'),
+        _ => anyhow::bail!("Delimiter \"{}\" should be a single ASCII character", str),
+    }
+}
+
+fn parse_locale(str: &str) -> Result<chrono::Locale> {
+    chrono::Locale::try_from(str.replace('-', "_").as_str())
+        .map_err(|_| anyhow::anyhow!("Unknown locale \"{}\", expected one like fr_FR", str))
+}
+
+/// The URL to download when `--file` was given one instead of a path
+fn download_url(file: &std::path::Path) -> Option<&str> {
+    file.to_str()
+        .filter(|f| f.starts_with("https://") || f.starts_with("http://"))
+}
+
+fn is_spreadsheet(file: &std::path::Path) -> bool {
+    matches!(
+        file.extension().and_then(|e| e.to_str()),
+        Some("xlsx" | "xlsm" | "xls" | "ods")
+    )
+}
+
+fn round_to_hundredth(num: f64) -> f64 {
+    (num * 100.0).round() / 100.0
+}
+
+/// Hours to the hundredth, which is only done once time has been summed so
+/// that many short entries don't drift from what was tracked
+fn billed_hours(duration: &Duration) -> f64 {
+    round_to_hundredth(duration.num_seconds() as f64 / 3600.0)
+}
+
+fn hours_str(duration: &Duration) -> String {
+    format!("{:.2}h", duration.num_seconds() as f64 / 3600.0)
+}
+
+fn duration_str(duration: &Duration) -> String {
+    let seconds = duration.num_seconds();
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct InvoiceBuilder {
+    /// Time per project, kept exact until the invoice is built
+    project_time_logged: HashMap<String, Duration>,
+    entries: Vec<TimeEntry>,
+    /// The time billed for each of the entries, after estimates, rounding
+    /// and caps, and none for those that aren't billable
+    billed_time: Vec<Duration>,
+    /// The billable time as tracked, before any of it is adjusted
+    worked_time: Duration,
+    pay_rate: Money,
+    /// The taxes charged on the subtotal, in the order they're listed
+    taxes: Vec<Tax>,
+    tax_rounding: TaxRounding,
+    reverse_charge: Option<ReverseCharge>,
+    supplier: Option<Party>,
+    bill_to: Option<Party>,
+    number: Option<String>,
+    withholding: Option<f64>,
+    overtime: Option<OvertimeRule>,
+    day_rate: Option<DayRateRule>,
+    /// The part of `rated_time` from entries marked tax exempt, on projects
+    /// that are otherwise taxed
+    exempt_time: Vec<RatedTime>,
+    weekend_multiplier: Option<f64>,
+    holiday_multiplier: Option<f64>,
+    holidays: Vec<NaiveDate>,
+    cost_rate: Option<Money>,
+    /// The currency named on the invoice, when one was given
+    currency: Option<Currency>,
+    exchange: Option<ExchangeRate>,
+
+    /// Hours per project and the rate they're charged at, in the order first seen
+    rated_time: Vec<RatedTime>,
+    rate_schedule: Vec<ScheduledRate>,
+    tiers: Vec<PriceTier>,
+    items: Vec<FixedItem>,
+    /// How much of each project's own tiers has been used up, or of the
+    /// shared tiers under `None`
+    tier_time: HashMap<Option<String>, Duration>,
+    minimum: Option<MinimumCharge>,
+    issued: Option<NaiveDate>,
+    payment_terms: Option<u32>,
+    period: Option<(NaiveDate, NaiveDate)>,
+
+    columns: Columns,
+    csv_delimiter: Option<u8>,
+    csv_headers: Option<bool>,
+    encoding: Option<&'static encoding_rs::Encoding>,
+    fixed_width: Option<FixedWidth>,
+    aliases: HashMap<String, String>,
+    projects: HashMap<String, ProjectConfig>,
+    /// Time found against archived projects, and where it was moved to
+    archived_time: BTreeMap<String, (Duration, Option<String>)>,
+    long_entry_policy: LongEntryPolicy,
+    /// Skip rows that can't be read rather than failing the import
+    lenient: bool,
+    /// Entries longer than this are warned about
+    max_entry: Option<Duration>,
+    trim_overlaps: bool,
+    dedupe: bool,
+    rounding: Option<Rounding>,
+    /// Implausibly long entries and days fail the import rather than being warned about
+    strict: bool,
+    report: ImportReport,
+    merge_policy: MergePolicy,
+    estimate_all: bool,
+    estimate_share: Option<f64>,
+    /// The estimated time collected, before it's discounted
+    estimated_time: Duration,
+    /// Time left off the invoice as non-billable, per project
+    non_billable: BTreeMap<String, Duration>,
+    list_non_billable: bool,
+    /// The most time billed per project
+    caps: HashMap<String, Duration>,
+    /// Time past a project's cap, listed but not charged for
+    courtesy_time: BTreeMap<String, Duration>,
+    detailed: bool,
+    /// The only client whose entries are collected, when splitting by client
+    only_client: Option<String>,
+    tags: Vec<String>,
+    excluded_tags: Vec<String>,
+    included_projects: Vec<ProjectPattern>,
+    excluded_projects: Vec<ProjectPattern>,
+    period_end: Option<NaiveDate>,
+    /// The first and last days a file's entries are invoiced for, leaving out
+    /// entries dated outside them
+    date_filter: (Option<NaiveDate>, Option<NaiveDate>),
+    exclude_future: bool,
+    /// The time entries are checked against, the clock when unset
+    now: Option<NaiveDateTime>,
+    warnings: Vec<String>,
+}
+
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+pub struct Invoice {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    number: Option<String>,
+    /// Who the invoice is from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    supplier: Option<Party>,
+    /// Who the invoice is billed to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bill_to: Option<Party>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    issued: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    due: Option<NaiveDate>,
+    /// The first and last days the invoice covers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    period: Option<(NaiveDate, NaiveDate)>,
+
+    project_hours_logged: HashMap<String, f64>,
+    total_time: f64,
+    subtotal: Money,
+    taxes: Vec<TaxLine>,
+    /// How the taxes were rounded, so they can be worked out again
+    tax_rounding: TaxRounding,
+    total: Money,
+
+    pay_rate: Money,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: Option<Currency>,
+    /// Fixed-price items charged besides the hours
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    items: Vec<ItemLine>,
+    /// The days charged for when billing by the day, in place of the hours
+    #[serde(skip_serializing_if = "Option::is_none")]
+    days: Option<DaysBilled>,
+    /// The premium charged on hours past the overtime threshold
+    #[serde(skip_serializing_if = "Option::is_none")]
+    overtime: Option<Overtime>,
+    /// The difference charged to bring the invoice up to the client's minimum
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum_fee: Option<Money>,
+    /// Hours split by the rate they're charged at, only when a rate schedule
+    /// charged some of them at other than the pay rate
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    rated_lines: Vec<InvoiceLine>,
+    /// Hours per project that weren't charged for, when they were asked to be listed
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    non_billable: BTreeMap<String, f64>,
+    /// Hours per project past its cap, shown to the client but not charged for
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    courtesy: BTreeMap<String, f64>,
+    /// Discloses how much of the time was estimated rather than tracked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimate: Option<EstimateNote>,
+    /// The billed entries behind each project's hours, in a detailed invoice
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    entries: BTreeMap<String, Vec<EntryLine>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reverse_charge: Option<ReverseCharge>,
+    /// Income tax the client pays on the business's behalf, taken off the total
+    #[serde(skip_serializing_if = "Option::is_none")]
+    withholding: Option<Withholding>,
+    /// What's left to pay after the withholding, when there is one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    amount_payable: Option<Money>,
+    /// What the projects that aren't taxed came to, which the taxes leave out
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    tax_exempt: BTreeMap<String, Money>,
+    /// The totals again in the client's currency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    conversion: Option<Conversion>,
+    /// Internal only, so it's left out of every rendered format
+    #[serde(skip)]
+    cost_rate: Option<Money>,
+    /// The billable time as tracked, before estimates, rounding and caps,
+    /// which is what it cost to work
+    #[serde(skip)]
+    worked_time: Duration,
+}
+
+/// Hours on a project charged at one rate
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct InvoiceLine {
+    pub project: String,
+    pub rate: Money,
+    pub hours: f64,
+    /// Why the rate is higher than usual, when it is
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub surcharge: Option<Surcharge>,
+}
+
+/// The days hours are charged more for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Surcharge {
+    Weekend,
+    Holiday,
+}
+
+impl Surcharge {
+    /// When the hours were worked, as the invoice explains it
+    pub fn label(&self) -> &'static str {
+        match self {
+            Surcharge::Weekend => "on weekends",
+            Surcharge::Holiday => "on holidays",
+        }
+    }
+}
+
+/// Time charged at one rate, before it's rounded into an [`InvoiceLine`]
+#[derive(Debug, Clone, PartialEq)]
+struct RatedTime {
+    project: String,
+    rate: Money,
+    surcharge: Option<Surcharge>,
+    time: Duration,
+}
+
+/// Adds time to the line for its project, rate and surcharge, starting one if
+/// there isn't one yet
+fn add_to_lines(
+    lines: &mut Vec<RatedTime>,
+    project: &str,
+    rate: Money,
+    surcharge: Option<Surcharge>,
+    duration: &Duration,
+) {
+    match lines
+        .iter_mut()
+        .find(|l| l.project == project && l.rate == rate && l.surcharge == surcharge)
+    {
+        Some(line) => line.time += *duration,
+        None => lines.push(RatedTime {
+            project: project.to_owned(),
+            rate,
+            surcharge,
+            time: *duration,
+        }),
+    }
+}
+
+/// One entry listed under its project on a detailed invoice
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct EntryLine {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The hours tracked, before any estimate discount
+    pub hours: f64,
+}
+
+/// Estimated time on an invoice, and how much of it was billed
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct EstimateNote {
+    /// The estimated hours before they were discounted
+    pub hours: f64,
+    /// The fraction of those hours that was billed
+    pub billed_share: f64,
+}
+
+/// An invoice's totals in another currency
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Conversion {
+    #[serde(flatten)]
+    pub exchange: ExchangeRate,
+    pub subtotal: Money,
+    pub taxes: Vec<TaxLine>,
+    pub total: Money,
+}
+
+/// A tax charged on an invoice, such as GST
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Tax {
+    pub name: String,
+    pub percent: f64,
+    /// Whether it's charged on the subtotal plus the taxes listed before it,
+    /// like Québec's QST was on GST until 2013
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub compound: bool,
+    /// The business's registration number for the tax
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registration: Option<String>,
+}
+
+/// Who an invoice is from or billed to, as printed in its header
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Party {
+    pub name: Option<String>,
+    /// The address, a line at a time
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub address: Vec<String>,
+    /// A GST, VAT or other tax registration number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tax_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+impl Party {
+    fn new(name: &Option<String>, address: &[String], tax_id: &Option<String>) -> Option<Self> {
+        let party = Self {
+            name: name.clone(),
+            address: address.to_vec(),
+            tax_id: tax_id.clone(),
+            email: None,
+        };
+        (party != Self::default()).then_some(party)
+    }
+
+    /// The details given here, with the ones left out taken from `other`
+    fn or(self, other: &Party) -> Self {
+        Self {
+            name: self.name.or_else(|| other.name.clone()),
+            address: match self.address.is_empty() {
+                true => other.address.clone(),
+                false => self.address,
+            },
+            tax_id: self.tax_id.or_else(|| other.tax_id.clone()),
+            email: self.email.or_else(|| other.email.clone()),
+        }
+    }
+}
+
+/// Why an invoice charges no VAT, for a business client elsewhere in the EU
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ReverseCharge {
+    pub vat_id: String,
+    pub client_vat_id: String,
+    /// The statement Article 226(11a) of the VAT Directive requires
+    pub statement: &'static str,
+}
+
+const REVERSE_CHARGE_STATEMENT: &str = "Reverse charge: VAT is to be accounted for by the recipient under Article 196 of Council Directive 2006/112/EC";
+
+/// A fixed-price item as it's charged on the invoice
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ItemLine {
+    pub name: String,
+    pub amount: Money,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub tax_exempt: bool,
+}
+
+/// The half and full days charged for at a day rate
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DaysBilled {
+    pub full: u32,
+    pub half: u32,
+    pub rate: Money,
+    pub amount: Money,
+}
+
+impl DaysBilled {
+    /// The number of days, counting half days as half
+    pub fn count(&self) -> f64 {
+        self.full as f64 + self.half as f64 / 2.0
+    }
+}
+
+/// The hours worked past the overtime threshold, which are charged again at
+/// the part of the multiplier above one
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Overtime {
+    pub hours: f64,
+    /// The hours in a day or week after which time is overtime
+    pub after: f64,
+    pub per: OvertimePeriod,
+    pub multiplier: f64,
+    /// What's charged on top of the hours' usual rate
+    pub premium: Money,
+}
+
+/// Income tax withheld from an invoice, as Spanish IRPF or Italian ritenuta d'acconto is
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Withholding {
+    pub percent: f64,
+    /// The amount taken off, as a negative amount
+    pub amount: Money,
+}
+
+/// What a tax comes to on an invoice
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct TaxLine {
+    #[serde(flatten)]
+    pub tax: Tax,
+    pub amount: Money,
+}
+
+/// What an invoice earns against the internal cost of the hours behind it
+///
+/// This is for your own records and is never part of the invoice itself.
+#[derive(Debug, PartialEq)]
+pub struct Profitability {
+    pub cost_rate: Money,
+    pub cost: Money,
+    pub revenue: Money,
+    pub margin: Money,
+}
+
+impl InvoiceBuilder {
+    pub fn new(args: &Args) -> Self {
+        let today = Local::now().date_naive();
+        let (from, period_end) = args.date_bounds(today);
+        let columns = Columns::default();
+        let currency = args.currency.unwrap_or_default();
+
+        let mut taxes: Vec<Tax> = args
+            .gst
+            .map(|rate| Tax {
+                name: "GST".to_owned(),
+                percent: rate.percent,
+                compound: false,
+                registration: None,
+            })
+            .into_iter()
+            .chain(args.tax.iter().cloned())
+            .collect();
+        let mut warnings = Vec::new();
+        if let Some(gst) = args.gst.filter(|gst| gst.ambiguous) {
+            warnings.push(format!(
+                "GST was read as {}%, give it with a % sign if something else was meant",
+                gst.percent
+            ));
+        }
+        for (name, number) in &args.tax_number {
+            match taxes.iter_mut().find(|t| t.name.eq_ignore_ascii_case(name)) {
+                Some(tax) => tax.registration = Some(number.clone()),
+                None => warnings.push(format!(
+                    "No tax \"{}\" is charged, so its number {} is left off",
+                    name, number
+                )),
+            }
+        }
+
+        Self {
+            project_time_logged: HashMap::new(),
+            pay_rate: Money::new(args.pay_rate.unwrap_or_default(), currency),
+            taxes,
+            reverse_charge: args
+                .reverse_charge
+                .then(|| args.vat_id.clone().zip(args.client_vat_id.clone()))
+                .flatten()
+                .map(|(vat_id, client_vat_id)| ReverseCharge {
+                    vat_id,
+                    client_vat_id,
+                    statement: REVERSE_CHARGE_STATEMENT,
+                }),
+            supplier: Party::new(&args.supplier_name, &args.supplier_address, &args.vat_id),
+            bill_to: Party::new(
+                &args.bill_to_name,
+                &args.bill_to_address,
+                &args.client_vat_id,
+            ),
+            withholding: args.withholding,
+            items: args.item.clone(),
+            day_rate: args.day_rate.map(|rate| DayRateRule {
+                rate: Money::new(rate, currency),
+                full_day: Duration::seconds((args.full_day_hours * 3600.0).round() as i64),
+                half_day: Duration::seconds((args.half_day_hours * 3600.0).round() as i64),
+            }),
+            overtime: args.overtime_after.map(|hours| OvertimeRule {
+                after: Duration::seconds((hours * 3600.0).round() as i64),
+                per: args.overtime_per,
+                multiplier: args.overtime_multiplier,
+            }),
+            tax_rounding: args.tax_rounding,
+            weekend_multiplier: args.weekend_multiplier,
+            holiday_multiplier: args.holiday_multiplier,
+            cost_rate: args.cost_rate.map(|rate| Money::new(rate, currency)),
+            currency: args.currency,
+            exchange: args
+                .convert_to
+                .zip(args.exchange_rate)
+                .map(|(to, rate)| ExchangeRate {
+                    to,
+                    rate,
+                    date: None,
+                }),
+
+            columns: Columns {
+                project: args.project_column.clone().unwrap_or(columns.project),
+                duration: args.duration_column.clone().unwrap_or(columns.duration),
+                span: args.start_column.clone().zip(args.end_column.clone()),
+                duration_format: args.duration_format,
+                billable: args.billable_column.clone(),
+                tax_exempt: args.tax_exempt_column.clone(),
+                tags: args.tags_column.clone(),
+                notes: args.notes_column.clone(),
+                client: args.client_column.clone(),
+                date: args.date_column.clone(),
+                rate: args.rate_column.clone(),
+            },
+            csv_delimiter: match args.tab {
+                true => Some(b'\t'),
+                false => args.delimiter,
+            },
+            csv_headers: args.no_headers.then_some(false),
+            encoding: args.encoding,
+            fixed_width: args.fixed_width.clone(),
+            aliases: args.alias.iter().cloned().collect(),
+            long_entry_policy: args.long_entry,
+            lenient: args.lenient,
+            max_entry: args
+                .max_entry_hours
+                .filter(|hours| *hours > 0.0)
+                .map(|hours| Duration::seconds((hours * 3600.0).round() as i64)),
+            strict: args.strict,
+            trim_overlaps: args.trim_overlaps,
+            dedupe: args.dedupe,
+            rounding: args.round_increment.map(|increment| Rounding {
+                increment,
+                scope: args.round_per,
+            }),
+            merge_policy: args.merge,
+            estimate_all: args.estimated,
+            estimate_share: args.estimate_share,
+            list_non_billable: args.list_non_billable,
+            caps: args
+                .cap
+                .iter()
+                .map(|(project, hours)| {
+                    let cap = Duration::seconds((hours * 3600.0).round() as i64);
+                    (project.clone(), cap)
+                })
+                .collect(),
+            detailed: args.detailed,
+            tags: args.tag.clone(),
+            excluded_tags: args.exclude_tag.clone(),
+            included_projects: args.included_projects.clone(),
+            excluded_projects: args.excluded_projects.clone(),
+            period_end,
+            exclude_future: args.no_future,
+            // Fetched entries are already limited to the range, and a file's
+            // entries outside it are left off like they would have been
+            date_filter: match args.source {
+                Some(_) => (None, None),
+                None => (from, period_end),
+            },
+            warnings,
+            ..Default::default()
+        }
+    }
+
+    /// The entries collected so far, in the order they were imported
+    pub fn entries(&self) -> &[TimeEntry] {
+        &self.entries
+    }
+
+    /// All the time collected so far, exactly as tracked
+    pub fn tracked_time(&self) -> Duration {
+        self.project_time_logged
+            .values()
+            .fold(Duration::zero(), |total, time| total + *time)
+    }
+
+    /// How billed time is rounded, if it is
+    pub fn rounding(&self) -> Option<&Rounding> {
+        self.rounding.as_ref()
+    }
+
+    /// The rows a lenient import skipped, each of which is also a warning
+    pub fn import_report(&self) -> &ImportReport {
+        &self.report
+    }
+
+    /// Problems noticed in the time entries that didn't stop the invoice being built
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// The clients named by the entries collected so far
+    pub fn clients(&self) -> std::collections::BTreeSet<&str> {
+        self.entries
+            .iter()
+            .filter_map(|e| e.client.as_deref())
+            .collect()
+    }
+
+    /// Only collects the entries for one client, leaving out the rest
+    ///
+    /// This has to be set before importing.
+    pub fn only_client(&mut self, client: &str) -> &mut Self {
+        self.only_client = Some(client.to_owned());
+        self
+    }
+
+    /// Charges entries dated on or after each rate's effective date at that rate
+    ///
+    /// A project's own rates take precedence over general ones. Entries that
+    /// no rate applies to, or that aren't dated, are charged the pay rate.
+    /// This has to be set before importing.
+    pub fn schedule_rates(&mut self, rates: &[ScheduledRate]) -> &mut Self {
+        self.rate_schedule = rates.to_vec();
+        self
+    }
+
+    /// Fills in the supplier's and client's details not given on the command
+    /// line from the config file
+    pub fn parties(&mut self, supplier: Option<&Party>, bill_to: Option<&Party>) -> &mut Self {
+        if let Some(supplier) = supplier {
+            self.supplier = Some(self.supplier.take().unwrap_or_default().or(supplier));
+        }
+        if let Some(bill_to) = bill_to {
+            self.bill_to = Some(self.bill_to.take().unwrap_or_default().or(bill_to));
+        }
+        self
+    }
+
+    /// Adds fixed-price items to the ones given on the command line
+    pub fn add_items(&mut self, items: &[FixedItem]) -> &mut Self {
+        self.items.extend_from_slice(items);
+        self
+    }
+
+    /// Sets the tiers hours are charged through, before entries are collected
+    pub fn price_tiers(&mut self, tiers: &[PriceTier]) -> &mut Self {
+        self.tiers = tiers.to_vec();
+        self
+    }
+
+    /// Sets the public holidays `--holiday-multiplier` applies on, before
+    /// entries are collected
+    pub fn observe_holidays(&mut self, dates: &[NaiveDate]) -> &mut Self {
+        self.holidays = dates.to_vec();
+        self
+    }
+
+    /// Dates the invoice, with payment due `payment_terms` days later
+    ///
+    /// Without a period the invoice covers the days its entries are dated.
+    pub fn date_invoice(
+        &mut self,
+        issued: NaiveDate,
+        payment_terms: Option<u32>,
+        period: Option<(NaiveDate, NaiveDate)>,
+    ) -> &mut Self {
+        self.issued = Some(issued);
+        self.payment_terms = payment_terms;
+        self.period = period;
+        self
+    }
+
+    /// Gives the invoice its number
+    pub fn number_invoice(&mut self, number: String) -> &mut Self {
+        self.number = Some(number);
+        self
+    }
+
+    /// Adds the totals converted at an exchange rate to the invoice
+    pub fn convert(&mut self, exchange: ExchangeRate) -> &mut Self {
+        self.exchange = Some(exchange);
+        self
+    }
+
+    /// Flags time against archived projects, moving it to their successors
+    ///
+    /// This has to be set before importing.
+    pub fn project_settings(&mut self, projects: &HashMap<String, ProjectConfig>) -> &mut Self {
+        self.projects = projects.clone();
+        self
+    }
+
+    /// Warns about projects whose corrections take off more time than was
+    /// tracked, which are billed as no time rather than a credit
+    pub fn check_corrections(&mut self) -> &mut Self {
+        let mut overdrawn: Vec<(&String, &Duration)> = self
+            .project_time_logged
+            .iter()
+            .filter(|(_, time)| **time < Duration::zero())
+            .collect();
+        overdrawn.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (project, time) in overdrawn {
+            self.warnings.push(format!(
+                "Corrections take \"{}\" to {}, so it's billed as no time",
+                project,
+                hours_str(time)
+            ));
+        }
+
+        self
+    }
+
+    /// Warns about time that was tracked against archived projects
+    pub fn check_archived_projects(&mut self) -> &mut Self {
+        for (project, (duration, successor)) in &self.archived_time {
+            self.warnings.push(match successor {
+                Some(successor) => format!(
+                    "{} was tracked against archived project \"{}\" and is billed under \"{}\" instead",
+                    hours_str(duration),
+                    project,
+                    successor
+                ),
+                None => format!(
+                    "{} was tracked against archived project \"{}\", check it wasn't tracked by mistake",
+                    hours_str(duration),
+                    project
+                ),
+            });
+        }
+
+        self
+    }
+
+    /// Holds the invoice to the client's minimum subtotal
+    pub fn minimum_charge(&mut self, minimum: &MinimumCharge) -> &mut Self {
+        self.minimum = Some(minimum.clone());
+        self
+    }
+
+    /// Why the invoice shouldn't be raised yet, when the client's minimum
+    /// says to defer billing and the hours don't reach it
+    pub fn deferral(&self) -> Option<String> {
+        let minimum = self.minimum.as_ref()?;
+        let (subtotal, amount) = (self.build().subtotal, self.money(minimum.amount));
+
+        (minimum.shortfall == Shortfall::Defer && subtotal < amount).then(|| {
+            format!(
+                "The subtotal of {} is below the client's {} minimum, so bill these hours with next period's",
+                subtotal.with_symbol(),
+                amount.with_symbol()
+            )
+        })
+    }
+
+    /// An amount in the currency the invoice is in
+    fn money(&self, amount: f64) -> Money {
+        Money::new(amount, self.pay_rate.currency())
+    }
+
+    pub fn build(&self) -> Invoice {
+        let per_project = self.rounding.filter(|r| r.scope == RoundingScope::Project);
+        // Corrections can take a project below zero, but never into a credit
+        let billed = |time: &Duration| {
+            let time = (*time).max(Duration::zero());
+            per_project.map_or(time, |r| r.round_up(time))
+        };
+        let project_time: HashMap<&String, Duration> = self
+            .project_time_logged
+            .iter()
+            .map(|(project, time)| (project, billed(time)))
+            .collect();
+        let billed_time = project_time
+            .values()
+            .fold(Duration::zero(), |total, time| total + *time);
+        let total_time = billed_hours(&billed_time);
+        let project_hours_logged: HashMap<String, f64> = project_time
+            .iter()
+            .map(|(project, time)| ((*project).clone(), billed_hours(time)))
+            .collect();
+
+        // Billing by the day leaves the hours' rates out altogether
+        let days = self.day_rate.map(|rule| self.days_billed(&rule));
+        let rated_time: Vec<(&RatedTime, Duration)> = match days.is_some()
+            || self
+                .rated_time
+                .iter()
+                .all(|l| l.rate == self.pay_rate && l.surcharge.is_none())
+        {
+            true => Vec::new(),
+            // Lines corrections took all the time off of aren't listed
+            false => self
+                .rated_time
+                .iter()
+                .filter(|l| !l.time.is_zero())
+                .map(|l| (l, billed(&l.time)))
+                .collect(),
+        };
+        let mut subtotal = match (&days, rated_time.is_empty()) {
+            (Some(days), _) => days.amount,
+            (None, true) => self.pay_rate.for_time(billed_time),
+            (None, false) => rated_time
+                .iter()
+                .fold(self.money(0.0), |subtotal, (l, time)| {
+                    subtotal + l.rate.for_time(*time)
+                }),
+        };
+        let rated_lines = rated_time
+            .into_iter()
+            .map(|(l, time)| InvoiceLine {
+                project: l.project.clone(),
+                rate: l.rate,
+                hours: billed_hours(&time),
+                surcharge: l.surcharge,
+            })
+            .collect();
+
+        let overtime = self.overtime().filter(|_| days.is_none());
+        if let Some((overtime, _)) = &overtime {
+            subtotal += overtime.premium;
+        }
+
+        // Only invoices with some hours on them are topped up
+        let minimum_fee = self
+            .minimum
+            .as_ref()
+            .map(|m| (m.shortfall, self.money(m.amount)))
+            .filter(|(shortfall, amount)| {
+                *shortfall == Shortfall::Fee && total_time > 0.0 && subtotal < *amount
+            })
+            .map(|(_, amount)| amount - subtotal);
+        subtotal += minimum_fee.unwrap_or(self.money(0.0));
+
+        // Items are charged in full, so they aren't counted towards the minimum
+        let items: Vec<ItemLine> = self
+            .items
+            .iter()
+            .map(|item| ItemLine {
+                name: item.name.clone(),
+                amount: self.money(item.amount),
+                tax_exempt: item.tax_exempt,
+            })
+            .collect();
+        for item in &items {
+            subtotal += item.amount;
+        }
+
+        let mut tax_exempt = match days {
+            Some(_) => BTreeMap::new(),
+            None => self.tax_exempt(billed),
+        };
+        let premiums = overtime.as_ref().map_or(&[][..], |(_, p)| &p[..]);
+        for (project, premium) in premiums.iter().filter(|(p, _)| self.project_exempt(p)) {
+            if let Some(exempt) = tax_exempt.get_mut(project) {
+                *exempt += *premium;
+            }
+        }
+        for item in items.iter().filter(|i| i.tax_exempt) {
+            *tax_exempt
+                .entry(item.name.clone())
+                .or_insert(self.money(0.0)) += item.amount;
+        }
+        let taxable = tax_exempt
+            .values()
+            .fold(subtotal, |taxable, a| taxable - *a);
+        let mut taxed_lines: Vec<Money> = match &days {
+            Some(days) => vec![days.amount],
+            None => self
+                .rated_time
+                .iter()
+                .map(|l| self.taxed_amount(l, billed))
+                .filter(|amount| amount.minor() != 0)
+                .chain(
+                    premiums
+                        .iter()
+                        .filter(|(project, _)| !self.project_exempt(project))
+                        .map(|(_, premium)| *premium),
+                )
+                .collect(),
+        };
+        taxed_lines.extend(minimum_fee);
+        taxed_lines.extend(items.iter().filter(|i| !i.tax_exempt).map(|i| i.amount));
+        let taxes = self.tax_lines(taxable, &taxed_lines);
+        let total = taxes.iter().fold(subtotal, |total, t| total + t.amount);
+        let withholding = self.withholding.map(|percent| Withholding {
+            percent,
+            amount: -subtotal.times(percent / 100.0),
+        });
+
+        // A file's period runs between the dates it was limited to, or
+        // otherwise the first and last entry billed
+        let period = self.period.or_else(|| {
+            let dates = self.entries.iter().filter_map(|e| e.date);
+            let (from, to) = self.date_filter;
+            Some((from.or(dates.clone().min())?, to.or(dates.max())?))
+        });
+
+        Invoice {
+            number: self.number.clone(),
+            supplier: self.supplier.clone(),
+            bill_to: self.bill_to.clone(),
+            issued: self.issued,
+            due: self
+                .issued
+                .zip(self.payment_terms)
+                .map(|(issued, days)| issued + Duration::days(days.into())),
+            period: self.issued.and(period),
+
+            project_hours_logged,
+            total_time,
+            subtotal,
+            taxes,
+            tax_rounding: self.tax_rounding,
+            total,
+
+            pay_rate: self.pay_rate,
+            currency: self.currency,
+            items,
+            days,
+            overtime: overtime.map(|(overtime, _)| overtime),
+            minimum_fee,
+            rated_lines,
+            courtesy: self
+                .courtesy_time
+                .iter()
+                .map(|(project, time)| (project.clone(), billed_hours(time)))
+                .collect(),
+            non_billable: match self.list_non_billable {
+                true => self
+                    .non_billable
+                    .iter()
+                    .map(|(project, duration)| (project.clone(), billed_hours(duration)))
+                    .collect(),
+                false => BTreeMap::new(),
+            },
+            estimate: (self.estimated_time > Duration::zero()).then(|| EstimateNote {
+                hours: billed_hours(&self.estimated_time),
+                billed_share: self.estimate_share.unwrap_or(1.0),
+            }),
+            entries: match self.detailed {
+                true => self.entry_lines(),
+                false => BTreeMap::new(),
+            },
+            tax_exempt,
+            reverse_charge: self.reverse_charge.clone(),
+            amount_payable: withholding.as_ref().map(|w| total + w.amount),
+            withholding,
+            // Tax is worked out again in the other currency, so the converted
+            // total is what its lines add up to
+            conversion: self.exchange.map(|exchange| {
+                let subtotal = exchange.convert(subtotal);
+                let lines: Vec<Money> = taxed_lines.iter().map(|l| exchange.convert(*l)).collect();
+                let taxes = self.tax_lines(exchange.convert(taxable), &lines);
+                Conversion {
+                    exchange,
+                    subtotal,
+                    total: taxes.iter().fold(subtotal, |total, t| total + t.amount),
+                    taxes,
+                }
+            }),
+            cost_rate: self.cost_rate,
+            worked_time: self.worked_time,
+        }
+    }
+
+    /// Counts each day time was billed on as a full or half day, leaving out
+    /// days with less than a half day's time
+    fn days_billed(&self, rule: &DayRateRule) -> DaysBilled {
+        let mut logged: BTreeMap<NaiveDate, Duration> = BTreeMap::new();
+        for (entry, billed) in self.entries.iter().zip(&self.billed_time) {
+            if let Some(date) = entry.date {
+                *logged.entry(date).or_insert_with(Duration::zero) += *billed;
+            }
+        }
+
+        let full = logged.values().filter(|t| **t >= rule.full_day).count() as u32;
+        let half = logged
+            .values()
+            .filter(|t| **t < rule.full_day && **t >= rule.half_day)
+            .count() as u32;
+        DaysBilled {
+            full,
+            half,
+            rate: rule.rate,
+            amount: rule.rate.times(full as f64 + half as f64 / 2.0),
+        }
+    }
+
+    /// The overtime premium, and what it comes to per project so tax
+    /// exemptions can be applied to it
+    fn overtime(&self) -> Option<(Overtime, Vec<(String, Money)>)> {
+        let rule = self.overtime?;
+        let mut worked: Vec<(NaiveDate, &TimeEntry, Duration)> = self
+            .entries
+            .iter()
+            .zip(&self.billed_time)
+            .filter(|(e, _)| e.billable)
+            .filter_map(|(e, billed)| Some((e.date?, e, *billed)))
+            .collect();
+        worked.sort_by_key(|(date, e, _)| (*date, e.start));
+
+        // Entries are counted in the order they were worked, so an entry that
+        // crosses the threshold is only partly overtime, and a correction
+        // takes off the overtime it brings the total back under
+        let mut period_time: HashMap<(i32, u32), Duration> = HashMap::new();
+        let mut overtime: Vec<RatedTime> = Vec::new();
+        for (date, entry, billed) in worked {
+            let before = period_time
+                .entry(rule.per.of(date))
+                .or_insert_with(Duration::zero);
+            let over_before = (*before - rule.after).max(Duration::zero());
+            *before += billed;
+            let over = (*before - rule.after).max(Duration::zero()) - over_before;
+            if over.is_zero() {
+                continue;
+            }
+
+            let rate = self.rate_for(entry);
+            match overtime
+                .iter_mut()
+                .find(|t| t.project == entry.project && t.rate == rate)
+            {
+                Some(time) => time.time += over,
+                None => overtime.push(RatedTime {
+                    project: entry.project.clone(),
+                    rate,
+                    surcharge: None,
+                    time: over,
+                }),
+            }
+        }
+        overtime.retain(|t| !t.time.is_zero());
+        if overtime.is_empty() {
+            return None;
+        }
+
+        let premiums: Vec<(String, Money)> = overtime
+            .iter()
+            .map(|t| {
+                let premium = t.rate.for_time(t.time).times(rule.multiplier - 1.0);
+                (t.project.clone(), premium)
+            })
+            .collect();
+        let time = overtime
+            .iter()
+            .fold(Duration::zero(), |total, t| total + t.time);
+        Some((
+            Overtime {
+                hours: billed_hours(&time),
+                after: billed_hours(&rule.after),
+                per: rule.per,
+                multiplier: rule.multiplier,
+                premium: premiums
+                    .iter()
+                    .fold(self.money(0.0), |total, (_, premium)| total + *premium),
+            },
+            premiums,
+        ))
+    }
+
+    /// What each project set as tax exempt in the config file was charged
+    fn tax_exempt(&self, billed: impl Fn(&Duration) -> Duration) -> BTreeMap<String, Money> {
+        let mut exempt: BTreeMap<String, Money> = BTreeMap::new();
+        let lines = self
+            .rated_time
+            .iter()
+            .filter(|l| self.project_exempt(&l.project))
+            .chain(&self.exempt_time);
+        for line in lines {
+            let amount = line.rate.for_time(billed(&line.time));
+            let total = exempt
+                .entry(line.project.clone())
+                .or_insert(self.money(0.0));
+            *total += amount;
+        }
+        exempt.retain(|_, amount| amount.minor() > 0);
+
+        exempt
+    }
+
+    /// Whether a project is set as tax exempt in the config file
+    fn project_exempt(&self, project: &str) -> bool {
+        self.projects.get(project).is_some_and(|p| p.tax_exempt)
+    }
+
+    /// The part of a line charged for time that isn't tax exempt
+    fn taxed_amount(&self, line: &RatedTime, billed: impl Fn(&Duration) -> Duration) -> Money {
+        if self.project_exempt(&line.project) {
+            return self.money(0.0);
+        }
+        let exempt = self
+            .exempt_time
+            .iter()
+            .find(|l| {
+                l.project == line.project && l.rate == line.rate && l.surcharge == line.surcharge
+            })
+            .map_or(self.money(0.0), |l| l.rate.for_time(billed(&l.time)));
+
+        line.rate.for_time(billed(&line.time)) - exempt
+    }
+
+    /// What each tax comes to on the taxable amount, in the order they're listed
+    ///
+    /// When tax is rounded per line it's worked out on each of the lines
+    /// making up that amount instead, and those are added up.
+    fn tax_lines(&self, taxable: Money, lines: &[Money]) -> Vec<TaxLine> {
+        // The client pays the VAT, but the invoice still has to show none was charged
+        if self.reverse_charge.is_some() {
+            let vat = Tax {
+                name: "VAT".to_owned(),
+                percent: 0.0,
+                compound: false,
+                registration: None,
+            };
+            return match self.taxes.is_empty() {
+                true => vec![vat],
+                false => self
+                    .taxes
+                    .iter()
+                    .map(|t| Tax {
+                        percent: 0.0,
+                        ..t.clone()
+                    })
+                    .collect(),
+            }
+            .into_iter()
+            .map(|tax| TaxLine {
+                tax,
+                amount: Money::zero(taxable.currency()),
+            })
+            .collect();
+        }
+
+        let bases = match self.tax_rounding {
+            TaxRounding::Total => std::slice::from_ref(&taxable),
+            TaxRounding::Line => lines,
+        };
+        let mut amounts = vec![Money::zero(taxable.currency()); self.taxes.len()];
+        for base in bases {
+            let mut on_line: Vec<Money> = Vec::new();
+            for tax in &self.taxes {
+                let base = match tax.compound {
+                    true => on_line.iter().fold(*base, |base, amount| base + *amount),
+                    false => *base,
+                };
+                on_line.push(base.times(tax.percent / 100.0));
+            }
+            for (amount, on_line) in amounts.iter_mut().zip(on_line) {
+                *amount += on_line;
+            }
+        }
+
+        self.taxes
+            .iter()
+            .zip(amounts)
+            .map(|(tax, amount)| TaxLine {
+                tax: tax.clone(),
+                amount,
+            })
+            .collect()
+    }
+
+    /// The billed entries grouped by project, in the order they were tracked
+    fn entry_lines(&self) -> BTreeMap<String, Vec<EntryLine>> {
+        let mut billed: Vec<_> = self.entries.iter().filter(|e| e.billable).collect();
+        billed.sort_by_key(|e| (e.date, e.start));
+
+        let mut lines: BTreeMap<String, Vec<EntryLine>> = BTreeMap::new();
+        for entry in billed {
+            lines
+                .entry(entry.project.clone())
+                .or_default()
+                .push(EntryLine {
+                    date: entry.date,
+                    description: entry.notes.clone(),
+                    hours: billed_hours(&entry.duration),
+                });
+        }
+
+        lines
+    }
+
+    pub fn add_project_duration(&mut self, project: &str, duration: &Duration) -> &mut Self {
+        self.worked_time += *duration;
+        self.add_rated_duration(project, self.pay_rate, None, duration)
+    }
+
+    fn add_rated_duration(
+        &mut self,
+        project: &str,
+        rate: Money,
+        surcharge: Option<Surcharge>,
+        duration: &Duration,
+    ) -> &mut Self {
+        add_to_lines(&mut self.rated_time, project, rate, surcharge, duration);
+
+        *self
+            .project_time_logged
+            .entry(project.to_owned())
+            .or_insert_with(Duration::zero) += *duration;
+
+        self
+    }
+
+    pub fn collect_time_entries<E: Clone + Into<TimeEntry>>(&mut self, entries: &[E]) -> &mut Self {
+        let mut undated = 0;
+        let mut undated_overtime = 0;
+        for entry in entries {
+            let mut entry: TimeEntry = entry.clone().into();
+            if !self.tags_allow(&entry) {
+                continue;
+            }
+            match (self.date_filter, entry.date) {
+                ((None, None), _) => {}
+                ((from, to), Some(date)) => {
+                    if from.is_some_and(|from| date < from) || to.is_some_and(|to| date > to) {
+                        continue;
+                    }
+                }
+                (_, None) => undated += 1,
+            }
+            if self
+                .only_client
+                .as_ref()
+                .is_some_and(|client| entry.client.as_ref() != Some(client))
+            {
+                continue;
+            }
+            entry.estimated |= self.estimate_all;
+            if let Some(alias) = self.aliases.get(&entry.project) {
+                entry.project = alias.clone();
+            }
+            if !self.project_allowed(&entry.project) {
+                continue;
+            }
+            if let Some(settings) = self.projects.get(&entry.project).filter(|p| p.archived) {
+                let (duration, successor) = self
+                    .archived_time
+                    .entry(entry.project.clone())
+                    .or_insert_with(|| (Duration::zero(), settings.successor.clone()));
+                *duration += entry.duration;
+                if let Some(successor) = successor {
+                    entry.project = successor.clone();
+                }
+            }
+
+            if let Some(problem) = self.future_entry_problem(&entry) {
+                if self.exclude_future {
+                    self.warnings.push(format!("Excluded {}", problem));
+                    continue;
+                }
+                self.warnings.push(format!(
+                    "Included {} (use --no-future to exclude it)",
+                    problem
+                ));
+            }
+
+            if !entry.billable {
+                *self
+                    .non_billable
+                    .entry(entry.project.clone())
+                    .or_insert_with(Duration::zero) += entry.duration;
+                self.entries.push(entry);
+                self.billed_time.push(Duration::zero());
+                continue;
+            }
 
-    /// The GST percentage for the invoice
-    #[arg(short, long)]
-    pub gst: Option<f64>,
+            self.worked_time += entry.duration;
+            let billed = match entry.estimated {
+                true => {
+                    self.estimated_time += entry.duration;
+                    let share = self.estimate_share.unwrap_or(1.0);
+                    Duration::milliseconds(
+                        (entry.duration.num_milliseconds() as f64 * share).round() as i64,
+                    )
+                }
+                false => entry.duration,
+            };
+            let billed = match self.rounding.filter(|r| r.scope == RoundingScope::Entry) {
+                Some(rounding) => rounding.round_up(billed),
+                None => billed,
+            };
+            let billed = self.capped(&entry.project, billed);
+            let surcharge = self.surcharge_for(&entry);
+            for (rate, time) in self.tiered_time(&entry, billed) {
+                let (rate, surcharge) = match surcharge {
+                    Some((surcharge, multiplier)) => (rate.times(multiplier), Some(surcharge)),
+                    None => (rate, None),
+                };
+                self.add_rated_duration(&entry.project, rate, surcharge, &time);
+                if entry.tax_exempt && !self.project_exempt(&entry.project) {
+                    add_to_lines(
+                        &mut self.exempt_time,
+                        &entry.project,
+                        rate,
+                        surcharge,
+                        &time,
+                    );
+                }
+            }
+            if self.overtime.is_some() && entry.date.is_none() {
+                undated_overtime += 1;
+            }
+            self.entries.push(entry);
+            self.billed_time.push(billed);
+        }
 
-    /// The CSV file to read from
-    #[arg(short, long, value_name = "FILE")]
-    pub file: PathBuf,
-}
+        if undated > 0 {
+            self.warnings.push(format!(
+                "Included {} entries with no date, which can't be checked against the invoice period (see --date-column)",
+                undated
+            ));
+        }
+        if undated_overtime > 0 {
+            self.warnings.push(format!(
+                "Left {} entries with no date out of the overtime hours, which are counted by date (see --date-column)",
+                undated_overtime
+            ));
+        }
 
-fn round_to_hundredth(num: f64) -> f64 {
-    (num * 100.0).round() / 100.0
-}
+        self
+    }
 
-#[derive(Debug, PartialEq)]
-pub struct InvoiceBuilder {
-    project_hours_logged: HashMap<String, f64>,
-    pay_rate: f64,
-    gst_rate: f64,
-}
+    /// Whether a project is one of those asked for, and none of those to leave out
+    fn project_allowed(&self, project: &str) -> bool {
+        (self.included_projects.is_empty()
+            || self.included_projects.iter().any(|p| p.matches(project)))
+            && !self.excluded_projects.iter().any(|p| p.matches(project))
+    }
 
-#[derive(Debug, PartialEq)]
-pub struct Invoice {
-    project_hours_logged: HashMap<String, f64>,
-    total_time: f64,
-    subtotal: f64,
-    gst: f64,
-    total: f64,
+    /// Whether an entry has one of the tags asked for, and none of the tags to leave out
+    fn tags_allow(&self, entry: &TimeEntry) -> bool {
+        let has = |wanted: &[String]| {
+            entry
+                .tags
+                .iter()
+                .any(|tag| wanted.iter().any(|w| w.eq_ignore_ascii_case(tag)))
+        };
 
-    gst_rate: f64,
-    pay_rate: f64,
-}
+        (self.tags.is_empty() || has(&self.tags)) && !has(&self.excluded_tags)
+    }
 
-impl InvoiceBuilder {
-    pub fn new(args: &Args) -> Self {
-        Self {
-            project_hours_logged: HashMap::new(),
-            pay_rate: args.pay_rate,
-            gst_rate: args.gst.unwrap_or(0.0),
+    /// Collects entries from an importer, applying the checks that can fail the import
+    fn collect_imported<E: Clone + Into<TimeEntry>>(&mut self, entries: &[E]) -> Result<&mut Self> {
+        let entries = self
+            .merge_policy
+            .merge(entries.iter().cloned().map(Into::into).collect())?;
+        let mut checked = Vec::with_capacity(entries.len());
+
+        for entry in entries {
+            if entry.duration <= Duration::days(1) {
+                checked.push(entry);
+                continue;
+            }
+
+            let description = format!(
+                "{} entry of \"{}\"{}",
+                hours_str(&entry.duration),
+                entry.project,
+                entry.date.map(|d| format!(" on {}", d)).unwrap_or_default()
+            );
+            match self.long_entry_policy {
+                LongEntryPolicy::Keep => {
+                    self.warnings.push(format!(
+                        "Kept {} which is longer than a day (see --long-entry)",
+                        description
+                    ));
+                    checked.push(entry);
+                }
+                LongEntryPolicy::Clamp => {
+                    self.warnings
+                        .push(format!("Clamped {} to 24h", description));
+                    checked.push(TimeEntry {
+                        duration: Duration::days(1),
+                        ..entry
+                    });
+                }
+                LongEntryPolicy::Split => {
+                    let days = entry.split_days();
+                    self.warnings
+                        .push(format!("Split {} across {} days", description, days.len()));
+                    checked.extend(days);
+                }
+                LongEntryPolicy::Error => {
+                    anyhow::bail!("Found {} which is longer than a day", description)
+                }
+            }
         }
+
+        self.check_duplicates(&mut checked);
+        self.check_overlaps(&mut checked);
+        let collected = self.entries.len();
+        self.collect_time_entries(&checked);
+        self.check_plausible_durations(collected)?;
+        self.check_dated_for_day_rate(collected)?;
+
+        Ok(self)
     }
 
-    pub fn build(&self) -> Invoice {
-        let total_time = round_to_hundredth(self.project_hours_logged.iter().map(|(_, t)| t).sum());
+    /// Warns about entries that are exact repeats of an earlier one, as happens
+    /// when exports are concatenated, removing them with --dedupe
+    fn check_duplicates(&mut self, entries: &mut Vec<TimeEntry>) {
+        let mut seen = std::collections::HashSet::new();
+        let mut repeated = Vec::new();
+        for (i, entry) in entries.iter().enumerate() {
+            let Some(start) = entry.start else {
+                continue;
+            };
+            if !seen.insert((entry.project.as_str(), start, entry.duration)) {
+                repeated.push(i);
+            }
+        }
+        if repeated.is_empty() {
+            return;
+        }
+
+        let time = repeated
+            .iter()
+            .map(|&i| entries[i].duration)
+            .fold(Duration::zero(), |total, d| total + d);
+        match self.dedupe {
+            true => {
+                self.warnings.push(format!(
+                    "Removed {} duplicated entries, taking off {}",
+                    repeated.len(),
+                    hours_str(&time)
+                ));
+                for i in repeated.into_iter().rev() {
+                    entries.remove(i);
+                }
+            }
+            false => self.warnings.push(format!(
+                "Found {} duplicated entries adding {}, which may be from concatenated exports (see --dedupe)",
+                repeated.len(),
+                hours_str(&time)
+            )),
+        }
+    }
 
-        let subtotal = round_to_hundredth(total_time * self.pay_rate);
-        let gst = round_to_hundredth(subtotal * self.gst_rate);
+    /// Warns about entries whose start times put them over each other, which
+    /// would bill the time twice, trimming the later one with --trim-overlaps
+    fn check_overlaps(&mut self, entries: &mut Vec<TimeEntry>) {
+        let mut timed: Vec<usize> = (0..entries.len())
+            .filter(|&i| entries[i].start.is_some())
+            .collect();
+        timed.sort_by_key(|&i| entries[i].start);
 
-        let total = subtotal + gst;
+        let mut contained = Vec::new();
+        let mut latest: Option<(usize, NaiveDateTime)> = None;
+        for i in timed {
+            let Some(start) = entries[i].start else {
+                continue;
+            };
+            let end = start + entries[i].duration;
+            let Some((earlier, earlier_end)) = latest.filter(|(_, e)| start < *e) else {
+                latest = Some((i, end));
+                continue;
+            };
+            // Exact repeats were already warned about as duplicates
+            let repeat = |a: &TimeEntry, b: &TimeEntry| {
+                (&a.project, a.start, a.duration) == (&b.project, b.start, b.duration)
+            };
+            if !self.trim_overlaps && repeat(&entries[i], &entries[earlier]) {
+                continue;
+            }
 
-        Invoice {
-            project_hours_logged: self.project_hours_logged.clone(),
-            total_time,
-            subtotal,
-            gst,
-            total,
+            let overlap = earlier_end.min(end) - start;
+            self.warnings.push(format!(
+                "The entry of \"{}\" at {} overlaps the one of \"{}\" at {} by {}{}",
+                entries[i].project,
+                start,
+                entries[earlier].project,
+                entries[earlier].start.unwrap_or(start),
+                hours_str(&overlap),
+                match self.trim_overlaps {
+                    true => ", which was taken off it",
+                    false => " (see --trim-overlaps)",
+                }
+            ));
 
-            gst_rate: self.gst_rate,
-            pay_rate: self.pay_rate,
+            if self.trim_overlaps {
+                match end > earlier_end {
+                    true => {
+                        entries[i].duration = end - earlier_end;
+                        entries[i].start = Some(earlier_end);
+                    }
+                    false => contained.push(i),
+                }
+            }
+            if end > earlier_end {
+                latest = Some((i, end));
+            }
+        }
+
+        contained.sort_unstable();
+        for i in contained.into_iter().rev() {
+            entries.remove(i);
         }
     }
 
-    pub fn add_project_duration(&mut self, project: &str, duration: &Duration) -> &mut Self {
-        if let Some(time) = self.project_hours_logged.get_mut(project) {
-            *time += round_to_hundredth(duration.num_seconds() as f64 / 3600.0)
-        } else {
-            self.project_hours_logged.insert(
-                project.to_owned(),
-                round_to_hundredth(duration.num_seconds() as f64 / 3600.0),
+    /// Refuses entries from `first` on that are billed but have no date when
+    /// billing by the day, since the days are counted from the dates
+    fn check_dated_for_day_rate(&self, first: usize) -> Result<()> {
+        if self.day_rate.is_none() {
+            return Ok(());
+        }
+        let undated = self.entries[first..]
+            .iter()
+            .zip(&self.billed_time[first..])
+            .filter(|(e, billed)| e.date.is_none() && !billed.is_zero())
+            .count();
+        if undated > 0 {
+            anyhow::bail!(
+                "--day-rate counts the days worked from the entries' dates, but {} billed entries have no date (see --date-column)",
+                undated
             );
         }
 
-        self
+        Ok(())
+    }
+
+    /// Warns about entries from `first` on that are long enough to probably be
+    /// a forgotten timer, or that add up to more than a day on one date
+    fn check_plausible_durations(&mut self, first: usize) -> Result<()> {
+        let entries = &self.entries[first..];
+        let mut problems = Vec::new();
+
+        // Entries over a day were already handled by --long-entry
+        if let Some(max) = self.max_entry {
+            for entry in entries
+                .iter()
+                .filter(|e| e.duration > max && e.duration <= Duration::days(1))
+            {
+                problems.push(format!(
+                    "The {} entry of \"{}\"{} is longer than {}, which is usually a timer left running (see --max-entry-hours)",
+                    hours_str(&entry.duration),
+                    entry.project,
+                    entry.date.map(|d| format!(" on {}", d)).unwrap_or_default(),
+                    hours_str(&max)
+                ));
+            }
+        }
+
+        let mut days: BTreeMap<NaiveDate, Duration> = BTreeMap::new();
+        for entry in entries {
+            if let Some(date) = entry.date {
+                *days.entry(date).or_insert_with(Duration::zero) += entry.duration;
+            }
+        }
+        for (date, total) in days.iter().filter(|(_, total)| **total > Duration::days(1)) {
+            problems.push(format!(
+                "The entries on {} add up to {}, more than a day",
+                date,
+                hours_str(total)
+            ));
+        }
+
+        if self.strict && !problems.is_empty() {
+            anyhow::bail!(
+                "{} (leave out --strict to bill it anyway)",
+                problems.join("; ")
+            );
+        }
+        self.warnings.extend(problems);
+
+        Ok(())
     }
 
-    pub fn collect_time_entries(&mut self, entries: &[(String, Duration)]) -> &mut Self {
-        for (project, duration) in entries {
-            self.add_project_duration(&project, duration);
+    /// Warns about project names that look like typos of each other, suggesting
+    /// an alias that merges the one with fewer hours into the other
+    pub fn check_project_names(&mut self) -> &mut Self {
+        let mut names: Vec<&str> = self
+            .project_time_logged
+            .keys()
+            .map(String::as_str)
+            .collect();
+        names.sort();
+
+        let mut warnings = Vec::new();
+        for (a, b) in checks::similar_project_names(&names) {
+            let (time_a, time_b) = (self.project_time_logged[a], self.project_time_logged[b]);
+            let (from, to) = if time_a <= time_b { (a, b) } else { (b, a) };
+
+            warnings.push(format!(
+                "Projects \"{}\" ({}) and \"{}\" ({}) look alike, add --alias \"{}={}\" if they're the same project",
+                a,
+                hours_str(&time_a),
+                b,
+                hours_str(&time_b),
+                from,
+                to
+            ));
         }
+        self.warnings.extend(warnings);
+
+        self
+    }
+
+    /// Warns when the invoice's rate or date falls outside the client's contract
+    ///
+    /// The invoice is dated at the end of its period, or today without `--to`.
+    pub fn check_contract(&mut self, contract: &config::ContractConfig) -> &mut Self {
+        let today = self
+            .now
+            .unwrap_or_else(|| Local::now().naive_local())
+            .date();
+        let date = self.period_end.unwrap_or(today);
+
+        self.warnings
+            .extend(checks::contract_problems(contract, self.pay_rate, date));
 
         self
     }
 
+    /// The rate an entry is charged at, its own if the source gave one, or else
+    /// the one under the rate schedule
+    fn rate_for(&self, entry: &TimeEntry) -> Money {
+        if let Some(rate) = entry.rate {
+            return self.money(rate);
+        }
+        let Some(date) = entry.date else {
+            return self.pay_rate;
+        };
+
+        let own_rates = self
+            .rate_schedule
+            .iter()
+            .any(|r| r.project.as_ref() == Some(&entry.project));
+        self.rate_schedule
+            .iter()
+            .filter(|r| match own_rates {
+                true => r.project.as_ref() == Some(&entry.project),
+                false => r.project.is_none(),
+            })
+            .filter(|r| r.from <= date)
+            .max_by_key(|r| r.from)
+            .map_or(self.pay_rate, |r| self.money(r.rate))
+    }
+
+    /// The part of an entry's billed time still under its project's cap, with
+    /// the rest set aside as courtesy time
+    ///
+    /// A correction takes time off the courtesy time first, since that was
+    /// never charged, and only what's left of it off the billed time.
+    fn capped(&mut self, project: &str, billed: Duration) -> Duration {
+        let Some(cap) = self.caps.get(project) else {
+            return billed;
+        };
+        if billed < Duration::zero() {
+            let Some(courtesy) = self.courtesy_time.get_mut(project) else {
+                return billed;
+            };
+            let returned = (-billed).min(*courtesy);
+            *courtesy -= returned;
+            if courtesy.is_zero() {
+                self.courtesy_time.remove(project);
+            }
+            return billed + returned;
+        }
+        let logged = self
+            .project_time_logged
+            .get(project)
+            .copied()
+            .unwrap_or_else(Duration::zero);
+        let allowed = (*cap - logged).clamp(Duration::zero(), billed);
+        if allowed < billed {
+            *self
+                .courtesy_time
+                .entry(project.to_owned())
+                .or_insert_with(Duration::zero) += billed - allowed;
+        }
+
+        allowed
+    }
+
+    /// Splits an entry's billed time across the tiers for its project, using
+    /// each up in the order entries are collected, with any time past the
+    /// last tier charged at the entry's usual rate
+    fn tiered_time(&mut self, entry: &TimeEntry, billed: Duration) -> Vec<(Money, Duration)> {
+        let own_tiers = self
+            .tiers
+            .iter()
+            .any(|t| t.project.as_ref() == Some(&entry.project));
+        let tiers: Vec<PriceTier> = self
+            .tiers
+            .iter()
+            .filter(|t| match own_tiers {
+                true => t.project.as_ref() == Some(&entry.project),
+                false => t.project.is_none(),
+            })
+            .cloned()
+            .collect();
+        // A rate the time source gave the entry is what was agreed for it
+        if tiers.is_empty() || entry.rate.is_some() {
+            return vec![(self.rate_for(entry), billed)];
+        }
+
+        let used = self
+            .tier_time
+            .entry(own_tiers.then(|| entry.project.clone()))
+            .or_insert_with(Duration::zero);
+        // A correction gives time back to the tiers it was last taken from
+        let (start, end) = (*used, *used + billed);
+        *used = end;
+        let (start, end) = (start.min(end), start.max(end));
+        let signed = |piece: Duration| match billed < Duration::zero() {
+            true => -piece,
+            false => piece,
+        };
+
+        let mut pieces = Vec::new();
+        let mut tier_start = start.min(Duration::zero());
+        for tier in &tiers {
+            let tier_end = tier.hours.map_or(end, |hours| {
+                tier_start.max(Duration::zero())
+                    + Duration::seconds((hours * 3600.0).round() as i64)
+            });
+            let piece = tier_end.min(end) - start.max(tier_start);
+            if piece > Duration::zero() {
+                pieces.push((self.money(tier.rate), signed(piece)));
+            }
+            tier_start = tier_end;
+            if tier_start >= end {
+                return pieces;
+            }
+        }
+        pieces.push((self.rate_for(entry), signed(end - start.max(tier_start))));
+
+        pieces
+    }
+
+    /// Whether an entry was worked on a day charged more for, and the
+    /// multiple of its rate charged, with holidays taking precedence
+    fn surcharge_for(&self, entry: &TimeEntry) -> Option<(Surcharge, f64)> {
+        let date = entry.date?;
+        if let Some(multiplier) = self
+            .holiday_multiplier
+            .filter(|_| self.holidays.contains(&date))
+        {
+            return Some((Surcharge::Holiday, multiplier));
+        }
+
+        self.weekend_multiplier
+            .filter(|_| matches!(date.weekday(), chrono::Weekday::Sat | chrono::Weekday::Sun))
+            .map(|multiplier| (Surcharge::Weekend, multiplier))
+    }
+
+    /// Describes why an entry looks like it was tracked in the future, usually
+    /// because the time tracker was set to the wrong time zone
+    fn future_entry_problem(&self, entry: &TimeEntry) -> Option<String> {
+        let now = self.now.unwrap_or_else(|| Local::now().naive_local());
+        let description = format!("{} of \"{}\"", hours_str(&entry.duration), entry.project);
+
+        if let Some(start) = entry.start.filter(|start| *start > now) {
+            return Some(format!(
+                "{} starting in the future at {}",
+                description, start
+            ));
+        }
+
+        match (entry.date, self.period_end) {
+            (Some(date), _) if date > now.date() => {
+                Some(format!("{} dated in the future on {}", description, date))
+            }
+            (Some(date), Some(end)) if date > end => Some(format!(
+                "{} dated {}, after the invoice period ends on {}",
+                description, date, end
+            )),
+            _ => None,
+        }
+    }
+
+    /// Imports time entries from the file or source selected on the command line
+    pub fn import(&mut self, args: &Args, config: &Config) -> Result<&mut Self> {
+        let today = Local::now().date_naive();
+        let (from, to) = args.date_bounds(today);
+        let (from, to) = import::date_range(from, to, today);
+
+        match (args.source, &args.file) {
+            (Some(Source::Activitywatch), _) => {
+                self.import_activitywatch(&args.activitywatch, from, to)
+            }
+            (Some(Source::Caldav), _) => {
+                let options = args.caldav.options(config.caldav.as_ref(), from, to)?;
+                self.import_caldav(&options, &args.ics)
+            }
+            (Some(Source::Clockify), _) => {
+                let clockify = config.clockify.as_ref().context(
+                    "A [clockify] section with an api_key and workspace_id is required in the config file",
+                )?;
+                let options = import::clockify::ClockifyOptions {
+                    api_key: clockify.api_key.clone(),
+                    workspace_id: clockify.workspace_id.clone(),
+                    from,
+                    to,
+                };
+                self.import_clockify(&options)
+            }
+            (Some(Source::Git), _) => {
+                let entries = import::git::estimate_entries(&args.git, from, to)
+                    .context("Unable to estimate hours from git history")?;
+                self.collect_imported(&entries)
+            }
+            (Some(Source::Google), _) => {
+                let options = args.google.options(config.google.as_ref(), from, to)?;
+                self.import_google(&options)
+            }
+            (Some(Source::Jira), _) => {
+                let options = args.jira.options(config.jira.as_ref(), from, to)?;
+                self.import_jira(&options)
+            }
+            (Some(Source::Wakatime), _) => {
+                let wakatime = config.wakatime.as_ref().context(
+                    "A [wakatime] section with an api_key is required in the config file",
+                )?;
+                let options = import::wakatime::WakaTimeOptions {
+                    api_key: wakatime.api_key.clone(),
+                    api_url: wakatime
+                        .api_url
+                        .clone()
+                        .unwrap_or_else(|| import::wakatime::DEFAULT_API_URL.to_owned()),
+                    from,
+                    to,
+                };
+                self.import_wakatime(&options)
+            }
+            (Some(Source::Sqlite), _) => {
+                let database = args
+                    .sqlite
+                    .sqlite_db
+                    .as_ref()
+                    .context("--sqlite-db is required for SQLite")?;
+                let query = args
+                    .sqlite
+                    .query
+                    .as_ref()
+                    .context("--query is required for SQLite")?;
+                self.import_sqlite(database, query)
+            }
+            (Some(Source::Toggl), _) => {
+                let toggl = config
+                    .toggl
+                    .as_ref()
+                    .context("A [toggl] section with an api_token and workspace_id is required in the config file")?;
+                let options = import::toggl::TogglOptions {
+                    api_token: toggl.api_token.clone(),
+                    workspace_id: toggl.workspace_id,
+                    from,
+                    to,
+                };
+                self.import_toggl(&options)
+            }
+            (None, Some(file)) => match download_url(file) {
+                Some(url) => {
+                    let download = import::remote::download(url, args.file_token.as_deref())?;
+                    let result = self.import_path(download.path(), args, from, to);
+                    download.remove();
+                    result
+                }
+                None => self.import_path(file, args, from, to),
+            },
+            (None, None) => anyhow::bail!("Either a file or a source is required"),
+        }
+    }
+
+    /// Imports a local file, passing along the flags that some formats take
+    fn import_path(
+        &mut self,
+        file: &PathBuf,
+        args: &Args,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<&mut Self> {
+        if file.extension().is_some_and(|e| e == "ics") {
+            self.import_ics(file, &args.ics, from, to)
+        } else if is_spreadsheet(file) {
+            self.import_spreadsheet(file, args.sheet.as_deref())
+        } else {
+            self.import_file(file)
+        }
+    }
+
+    pub fn import_activitywatch(
+        &mut self,
+        args: &ActivityWatchArgs,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<&mut Self> {
+        #[cfg(feature = "http")]
+        {
+            let entries = import::activitywatch::fetch_entries(args, from, to)
+                .context("Unable to import ActivityWatch events")?;
+            self.collect_imported(&entries)
+        }
+
+        #[cfg(not(feature = "http"))]
+        {
+            let _ = (args, from, to);
+            Err(missing_feature("Fetching from ActivityWatch", "http"))
+        }
+    }
+
+    pub fn import_clockify(
+        &mut self,
+        options: &import::clockify::ClockifyOptions,
+    ) -> Result<&mut Self> {
+        #[cfg(feature = "http")]
+        {
+            let entries = import::clockify::fetch_entries(options)
+                .context("Unable to import Clockify time entries")?;
+            self.collect_imported(&entries)
+        }
+
+        #[cfg(not(feature = "http"))]
+        {
+            let _ = options;
+            Err(missing_feature("Fetching from Clockify", "http"))
+        }
+    }
+
+    pub fn import_caldav(
+        &mut self,
+        options: &import::caldav::CaldavOptions,
+        args: &IcsArgs,
+    ) -> Result<&mut Self> {
+        #[cfg(feature = "http")]
+        {
+            let entries = import::caldav::fetch_entries(options, args)
+                .context("Unable to import CalDAV events")?;
+            self.collect_imported(&entries)
+        }
+
+        #[cfg(not(feature = "http"))]
+        {
+            let _ = (options, args);
+            Err(missing_feature("Fetching from CalDAV", "http"))
+        }
+    }
+
+    pub fn import_google(&mut self, options: &import::google::GoogleOptions) -> Result<&mut Self> {
+        #[cfg(feature = "http")]
+        {
+            let entries = import::google::fetch_entries(options)
+                .context("Unable to import Google Calendar events")?;
+            self.collect_imported(&entries)
+        }
+
+        #[cfg(not(feature = "http"))]
+        {
+            let _ = options;
+            Err(missing_feature("Fetching from Google Calendar", "http"))
+        }
+    }
+
+    pub fn import_jira(&mut self, options: &import::jira::JiraOptions) -> Result<&mut Self> {
+        #[cfg(feature = "http")]
+        {
+            let entries =
+                import::jira::fetch_entries(options).context("Unable to import Jira worklogs")?;
+            self.collect_imported(&entries)
+        }
+
+        #[cfg(not(feature = "http"))]
+        {
+            let _ = options;
+            Err(missing_feature("Fetching from Jira", "http"))
+        }
+    }
+
+    pub fn import_sqlite(&mut self, database: &std::path::Path, query: &str) -> Result<&mut Self> {
+        #[cfg(feature = "sqlite")]
+        {
+            let entries = import::sqlite::query_entries(database, query)
+                .context("Unable to import entries from SQLite")?;
+            self.collect_imported(&entries)
+        }
+
+        #[cfg(not(feature = "sqlite"))]
+        {
+            let _ = (database, query);
+            Err(missing_feature("Reading SQLite databases", "sqlite"))
+        }
+    }
+
+    pub fn import_toggl(&mut self, options: &import::toggl::TogglOptions) -> Result<&mut Self> {
+        #[cfg(feature = "http")]
+        {
+            let entries = import::toggl::fetch_entries(options)
+                .context("Unable to import Toggl time entries")?;
+            self.collect_imported(&entries)
+        }
+
+        #[cfg(not(feature = "http"))]
+        {
+            let _ = options;
+            Err(missing_feature("Fetching from Toggl", "http"))
+        }
+    }
+
+    pub fn import_wakatime(
+        &mut self,
+        options: &import::wakatime::WakaTimeOptions,
+    ) -> Result<&mut Self> {
+        #[cfg(feature = "http")]
+        {
+            let entries = import::wakatime::fetch_entries(options)
+                .context("Unable to import WakaTime summaries")?;
+            self.collect_imported(&entries)
+        }
+
+        #[cfg(not(feature = "http"))]
+        {
+            let _ = options;
+            Err(missing_feature("Fetching from WakaTime", "http"))
+        }
+    }
+
+    /// Imports time entries from a file, choosing the parser from its extension
+    pub fn import_file(&mut self, file: &PathBuf) -> Result<&mut Self> {
+        match file.extension().and_then(|e| e.to_str()) {
+            Some("json") => self.import_json(file),
+            Some("org") => self.import_org(file),
+            _ if is_spreadsheet(file) => self.import_spreadsheet(file, None),
+            Some("timelog" | "timeclock") => self.import_timelog(file),
+            Some("toml" | "yaml" | "yml") => self.import_timesheet(file),
+            _ if self.fixed_width.is_some() => self.import_fixed_width(file),
+            _ => self.import_csv(file),
+        }
+    }
+
+    pub fn import_json(&mut self, file: &PathBuf) -> Result<&mut Self> {
+        let contents = std::fs::read_to_string(file)
+            .with_context(|| format!("Unable to read from given file \"{:?}\"", file))?;
+
+        let entries =
+            import::json::parse_entries(&contents).context("Unable to parse JSON entries")?;
+        self.collect_imported(&entries)
+    }
+
+    pub fn import_org(&mut self, file: &PathBuf) -> Result<&mut Self> {
+        let contents = std::fs::read_to_string(file)
+            .with_context(|| format!("Unable to read from given file \"{:?}\"", file))?;
+
+        let entries =
+            import::org::parse_entries(&contents).context("Unable to parse org-mode entries")?;
+        self.collect_imported(&entries)
+    }
+
+    pub fn import_ics(
+        &mut self,
+        file: &PathBuf,
+        args: &IcsArgs,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<&mut Self> {
+        let contents = std::fs::read_to_string(file)
+            .with_context(|| format!("Unable to read from given file \"{:?}\"", file))?;
+
+        let entries = import::ics::parse_entries(&contents, args, from, to)
+            .context("Unable to parse calendar events")?;
+        self.collect_imported(&entries)
+    }
+
+    pub fn import_spreadsheet(&mut self, file: &PathBuf, sheet: Option<&str>) -> Result<&mut Self> {
+        #[cfg(feature = "xlsx")]
+        {
+            let entries = import::xlsx::read_entries(file, sheet, &self.columns)
+                .with_context(|| format!("Unable to read spreadsheet \"{:?}\"", file))?;
+            self.collect_imported(&entries)
+        }
+
+        #[cfg(not(feature = "xlsx"))]
+        {
+            let _ = (file, sheet);
+            Err(missing_feature("Reading spreadsheets", "xlsx"))
+        }
+    }
+
+    pub fn import_timesheet(&mut self, file: &PathBuf) -> Result<&mut Self> {
+        let contents = std::fs::read_to_string(file)
+            .with_context(|| format!("Unable to read from given file \"{:?}\"", file))?;
+
+        let entries = match file.extension().and_then(|e| e.to_str()) {
+            Some("toml") => import::timesheet::parse_toml(&contents),
+            _ => import::timesheet::parse_yaml(&contents),
+        }
+        .context("Unable to parse timesheet")?;
+        self.collect_imported(&entries)
+    }
+
+    pub fn import_timelog(&mut self, file: &PathBuf) -> Result<&mut Self> {
+        let contents = std::fs::read_to_string(file)
+            .with_context(|| format!("Unable to read from given file \"{:?}\"", file))?;
+
+        let entries =
+            import::timelog::parse_entries(&contents).context("Unable to parse timelog entries")?;
+        self.collect_imported(&entries)
+    }
+
+    pub fn import_fixed_width(&mut self, file: &PathBuf) -> Result<&mut Self> {
+        let Some(layout) = self.fixed_width.clone() else {
+            anyhow::bail!("No --fixed-width ranges were given");
+        };
+        let contents = std::fs::read(file)
+            .with_context(|| format!("Unable to read from given file \"{:?}\"", file))?;
+
+        let contents = import::decode(&contents, self.encoding);
+        let entries =
+            import::fixed::parse_entries(&contents, &layout, self.columns.duration_format)
+                .context("Unable to parse fixed-width entries")?;
+        self.collect_imported(&entries)
+    }
+
     pub fn import_csv(&mut self, file: &PathBuf) -> Result<&mut Self> {
         let contents = std::fs::read(file)
             .with_context(|| format!("Unable to read from given file \"{:?}\"", file))?;
 
-        let mut reader = csv::ReaderBuilder::new()
-            .has_headers(true)
-            .from_reader(contents.as_slice());
+        let contents = import::decode(&contents, self.encoding);
+        let delimiter = match file.extension().and_then(|e| e.to_str()) {
+            Some("tsv" | "tab") => self.csv_delimiter.or(Some(b'\t')),
+            _ => self.csv_delimiter,
+        };
+        let format = CsvFormat::sniff(contents.as_bytes(), delimiter, self.csv_headers);
+        let mut reader = format.reader(contents.as_bytes());
 
-        let entries =
-            Self::parse_csv_entries(&mut reader).context("Unable to parse CSV entries")?;
-        self.collect_time_entries(&entries);
+        if !self.lenient {
+            let entries = Self::parse_csv_entries(&mut reader, &self.columns)
+                .context("Unable to parse CSV entries")?;
+            return self.collect_imported(&entries);
+        }
 
-        Ok(self)
+        let rows = Self::parse_csv_rows(&mut reader, &self.columns)
+            .context("Unable to parse CSV entries")?;
+        self.collect_readable(rows)
+    }
+
+    /// Collects the rows that were read, reporting each of the others
+    fn collect_readable(
+        &mut self,
+        rows: Vec<std::result::Result<TimeEntry, BadRow>>,
+    ) -> Result<&mut Self> {
+        let mut entries = Vec::with_capacity(rows.len());
+        for row in rows {
+            match row {
+                Ok(entry) => entries.push(entry),
+                Err(bad) => {
+                    self.warnings.push(match bad.content.is_empty() {
+                        true => format!("Skipped line {}: {}", bad.line, bad.reason),
+                        false => format!(
+                            "Skipped line {} \"{}\": {}",
+                            bad.line, bad.content, bad.reason
+                        ),
+                    });
+                    self.report.skipped.push(bad);
+                }
+            }
+        }
+        self.collect_imported(&entries)
     }
 
+    /// Parses an `HH:MM:SS` duration, one written out like `1h 30m`, `90m` or
+    /// `1d 2h`, or an ISO 8601 duration like `PT1H30M`
+    ///
+    /// A leading minus makes the duration a correction that takes time off.
     fn parse_duration_str(str: &str) -> Result<Duration> {
+        if let Some(correction) = str.trim().strip_prefix('-') {
+            return Ok(-Self::parse_duration_str(correction)?);
+        }
+        if let Some(iso) = str.strip_prefix(['P', 'p']) {
+            return Self::parse_iso_duration(iso)
+                .with_context(|| format!("\"{}\" isn't an ISO 8601 duration like PT1H30M", str));
+        }
+        if !str.contains(':') && str.contains(|c: char| c.is_ascii_alphabetic()) {
+            return Self::parse_duration_words(str);
+        }
+
         let time_parts: Vec<&str> = str.split(':').collect();
+        if time_parts.len() < 3 {
+            anyhow::bail!("Expected a duration like HH:MM:SS, not \"{}\"", str);
+        }
 
         let hours: i64 = time_parts[0]
             .parse()
@@ -135,51 +3519,310 @@ impl InvoiceBuilder {
         Ok(duration)
     }
 
-    fn parse_csv_entries(reader: &mut Reader<&[u8]>) -> Result<Vec<(String, Duration)>> {
-        let entries: Vec<(String, Duration)> = reader
-            .records()
-            .filter_map(|r| r.ok())
-            .flat_map(|r| {
-                Ok::<(String, Duration), anyhow::Error>((
-                    r[0].to_owned(),
-                    Self::parse_duration_str(&r[3])
-                        .with_context(|| format!("Unable to parse duration \"{}\"", &r[3]))?,
-                ))
+    /// Parses what follows the `P` of an ISO 8601 duration, refusing years and
+    /// months since their length depends on the calendar
+    fn parse_iso_duration(str: &str) -> Result<Duration> {
+        let (date, time) = str.split_once(['T', 't']).unwrap_or((str, ""));
+        if str.is_empty() || str.ends_with(['T', 't']) {
+            anyhow::bail!("The duration has no amounts");
+        }
+
+        let mut total = Duration::zero();
+        for (part, units) in [(date, "WD"), (time, "HMS")] {
+            let mut rest = part;
+            while !rest.is_empty() {
+                let split = rest
+                    .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+                    .context("A number has no unit")?;
+                let (amount, tail) = rest.split_at(split);
+                let unit = tail.chars().next().unwrap_or_default().to_ascii_uppercase();
+                let amount: f64 = amount
+                    .replace(',', ".")
+                    .parse()
+                    .with_context(|| format!("Unable to parse \"{}\"", amount))?;
+                let seconds = match (units, unit) {
+                    ("WD", 'W') => 604_800.0,
+                    ("WD", 'D') => 86_400.0,
+                    ("HMS", 'H') => 3_600.0,
+                    ("HMS", 'M') => 60.0,
+                    ("HMS", 'S') => 1.0,
+                    _ => anyhow::bail!("Unsupported unit \"{}\"", unit),
+                };
+
+                total += Duration::milliseconds((amount * seconds * 1000.0).round() as i64);
+                rest = &tail[unit.len_utf8()..];
+            }
+        }
+
+        Ok(total)
+    }
+
+    /// Adds up amounts with units, where a day is 24 hours
+    fn parse_duration_words(str: &str) -> Result<Duration> {
+        let mut total = Duration::zero();
+        let mut rest = str.trim();
+
+        while !rest.is_empty() {
+            let split = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let (amount, tail) = rest.split_at(split);
+            let tail = tail.trim_start();
+            let split = tail
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(tail.len());
+            let (unit, tail) = tail.split_at(split);
+
+            let amount: f64 = amount
+                .parse()
+                .with_context(|| format!("Expected a number before \"{}\" in \"{}\"", unit, str))?;
+            let seconds = match unit.to_ascii_lowercase().as_str() {
+                "d" | "day" | "days" => 86_400.0,
+                "h" | "hr" | "hrs" | "hour" | "hours" => 3_600.0,
+                "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+                "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+                _ => anyhow::bail!("Unknown unit \"{}\" in duration \"{}\"", unit, str),
+            };
+
+            total += Duration::milliseconds((amount * seconds * 1000.0).round() as i64);
+            rest = tail.trim_start();
+        }
+
+        Ok(total)
+    }
+
+    /// Reads every row into an entry, failing on the first that can't be read
+    fn parse_csv_entries(reader: &mut Reader<&[u8]>, columns: &Columns) -> Result<Vec<TimeEntry>> {
+        Self::parse_csv_rows(reader, columns)?
+            .into_iter()
+            .map(|row| row.map_err(|bad| anyhow::anyhow!("{}", bad)))
+            .collect()
+    }
+
+    /// Reads each row into an entry, or the reason it couldn't be
+    fn parse_csv_rows(
+        reader: &mut Reader<&[u8]>,
+        columns: &Columns,
+    ) -> Result<Vec<std::result::Result<TimeEntry, BadRow>>> {
+        // Without a header row, columns can only be found by index
+        let headers: Vec<String> = match reader.has_headers() {
+            true => reader.headers()?.iter().map(str::to_owned).collect(),
+            false => Vec::new(),
+        };
+        let project = columns.project.position(&headers)?;
+        // Durations are either read from their column or worked out from
+        // start and end columns
+        enum Timing {
+            Duration(usize),
+            Span(usize, usize),
+        }
+        let timing = match &columns.span {
+            Some((start, end)) => Timing::Span(start.position(&headers)?, end.position(&headers)?),
+            None => Timing::Duration(columns.duration.position(&headers)?),
+        };
+        let billable = columns.billable_position(&headers)?;
+        let tax_exempt = columns.tax_exempt_position(&headers)?;
+        let tags = columns.tags_position(&headers)?;
+        let notes = columns.notes_position(&headers)?;
+        let client = columns.client_position(&headers)?;
+        let date = columns.date_position(&headers)?;
+        let rate = columns.rate_position(&headers)?;
+
+        let rows = reader.records().map(|r| {
+            let r = r.map_err(|err| BadRow {
+                line: err.position().map_or(0, |p| p.line()),
+                content: String::new(),
+                reason: err.to_string(),
+            })?;
+            let entry = (|| {
+                let mut entry = match timing {
+                    Timing::Duration(duration) => {
+                        let (Some(name), Some(time)) = (r.get(project), r.get(duration)) else {
+                            anyhow::bail!("The row has no column {} or {}", project, duration);
+                        };
+                        TimeEntry::new(
+                            name,
+                            columns.duration_format.parse(time).with_context(|| {
+                                format!("Unable to parse duration \"{}\"", time)
+                            })?,
+                        )
+                    }
+                    Timing::Span(start, end) => {
+                        let (Some(name), Some(started), Some(ended)) =
+                            (r.get(project), r.get(start), r.get(end))
+                        else {
+                            anyhow::bail!(
+                                "The row has no column {}, {} or {}",
+                                project,
+                                start,
+                                end
+                            );
+                        };
+                        let started = import::parse_timestamp(started)
+                            .context("Unable to parse the start")?;
+                        let ended =
+                            import::parse_timestamp(ended).context("Unable to parse the end")?;
+                        if ended < started {
+                            anyhow::bail!("The entry ends before it starts");
+                        }
+                        TimeEntry::new(name, ended - started).started_at_instant(&started)
+                    }
+                };
+                if let Some(flag) = billable.and_then(|b| r.get(b)) {
+                    entry.billable = parse_billable(flag)?;
+                }
+                if let Some(flag) = tax_exempt.and_then(|t| r.get(t)) {
+                    entry.tax_exempt = parse_exempt(flag)?;
+                }
+                if let Some(list) = tags.and_then(|t| r.get(t)) {
+                    entry.tags = parse_tags(list);
+                }
+                entry.notes = notes
+                    .and_then(|n| r.get(n))
+                    .map(str::trim)
+                    .filter(|n| !n.is_empty())
+                    .map(str::to_owned);
+                entry.client = client
+                    .and_then(|c| r.get(c))
+                    .map(str::trim)
+                    .filter(|c| !c.is_empty())
+                    .map(str::to_owned);
+                if let Some(day) = date.and_then(|d| r.get(d)).filter(|d| !d.trim().is_empty()) {
+                    entry.date = Some(import::parse_date(day)?);
+                }
+                if let Some(amount) = rate.and_then(|i| r.get(i)) {
+                    entry.rate = parse_rate(amount)?;
+                }
+                Ok::<TimeEntry, anyhow::Error>(entry)
+            })();
+
+            entry.map_err(|err| BadRow {
+                line: r.position().map_or(0, |p| p.line()),
+                content: r.iter().collect::<Vec<_>>().join(","),
+                reason: format!("{:#}", err),
             })
-            .collect();
+        });
 
-        Ok(entries)
+        Ok(rows.collect())
     }
 }
 
-impl std::fmt::Display for Invoice {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let mut output = String::new();
+/// An evenly spread sample of imported entries, for checking they were read correctly
+#[derive(Debug, PartialEq)]
+pub struct EntryPreview<'a> {
+    pub sample: Vec<&'a TimeEntry>,
+    pub total: usize,
+}
 
-        // Format the time entries
-        output.push_str(&format!("{:<30} {:>10}\n", "Project", "Hours"));
-        output.push_str(&format!("{:-<41}\n", ""));
-        for (project, hours) in &self.project_hours_logged {
-            output.push_str(&format!("{:<30} {:>10.2}\n", project, hours));
+impl<'a> EntryPreview<'a> {
+    /// Picks `count` entries spaced evenly from first to last
+    pub fn new(entries: &'a [TimeEntry], count: usize) -> Self {
+        let sample = match (entries.len(), count) {
+            (0, _) | (_, 0) => Vec::new(),
+            (len, count) if count >= len => entries.iter().collect(),
+            (len, 1) => vec![&entries[len / 2]],
+            (len, count) => (0..count)
+                .map(|i| &entries[i * (len - 1) / (count - 1)])
+                .collect(),
+        };
+
+        Self {
+            sample,
+            total: entries.len(),
         }
+    }
+}
+
+impl std::fmt::Display for EntryPreview<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut output = String::new();
 
-        // Format the totals
         output.push_str(&format!(
-            "\n{:<30} {:>10.2}\n\n",
-            "Total Time (h)", self.total_time
+            "Previewing {} of {} entries\n",
+            self.sample.len(),
+            self.total
         ));
         output.push_str(&format!(
-            "{:<30} {:>10.2}\n",
-            &format!("Subtotal at ${}/hr", self.pay_rate),
-            self.subtotal
+            "{:<30} {:>10} {:>17}\n",
+            "Project", "Duration", "Date"
         ));
+        output.push_str(&format!("{:-<59}\n", ""));
+        for entry in &self.sample {
+            let date = match (entry.start, entry.date) {
+                (Some(start), _) => start.format("%Y-%m-%d %H:%M").to_string(),
+                (None, Some(date)) => date.to_string(),
+                (None, None) => "-".to_owned(),
+            };
+            output.push_str(&format!(
+                "{:<30} {:>10} {:>17}\n",
+                entry.project,
+                duration_str(&entry.duration),
+                date
+            ));
+        }
+
+        write!(f, "{}", output)
+    }
+}
+
+impl Invoice {
+    /// The total hours billed
+    pub fn total_time(&self) -> f64 {
+        self.total_time
+    }
+
+    /// The amount due, including GST
+    pub fn total(&self) -> Money {
+        self.total
+    }
+
+    /// What the client pays, which is less than the total when they withhold tax
+    pub fn amount_payable(&self) -> Money {
+        self.amount_payable.unwrap_or(self.total)
+    }
+
+    /// Compares the invoice's subtotal to the cost of its hours, if a cost rate was given
+    ///
+    /// GST is collected on behalf of the government so it isn't counted as
+    /// revenue, and the cost is of the hours as tracked rather than billed.
+    pub fn profitability(&self) -> Option<Profitability> {
+        let cost_rate = self.cost_rate?;
+        let cost = cost_rate.for_time(self.worked_time);
+
+        Some(Profitability {
+            cost_rate,
+            cost,
+            revenue: self.subtotal,
+            margin: self.subtotal - cost,
+        })
+    }
+}
+
+impl std::fmt::Display for Profitability {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut output = String::new();
+
+        output.push_str(&format!("{:<30} {:>10}\n", "Revenue", self.revenue));
         output.push_str(&format!(
-            "{:<30} {:>10.2}\n",
-            &format!("GST at {}%", self.gst_rate * 100.0),
-            self.gst
+            "{:<30} {:>10}\n",
+            &format!("Internal cost at {}", self.cost_rate.per_hour()),
+            self.cost
         ));
-        output.push_str(&format!("{:<30} {:>10.2}\n", "TOTAL", self.total));
+        output.push_str(&format!("{:<30} {:>10}\n", "Margin", self.margin));
+        if self.revenue.minor() != 0 {
+            output.push_str(&format!(
+                "{:<30} {:>9.1}%\n",
+                "Margin (%)",
+                self.margin.as_f64() / self.revenue.as_f64() * 100.0
+            ));
+        }
 
         write!(f, "{}", output)
     }
 }
+
+impl std::fmt::Display for Invoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", render::text(self, &RenderOptions::default()))
+    }
+}
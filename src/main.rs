@@ -1,12 +1,307 @@
-use clap::Parser;
-use pint_rs::{Args, Invoice, InvoiceBuilder};
+use chrono::{Datelike, Duration, Local};
+use clap::{Parser, ValueEnum};
+use pint_rs::config::{ClientConfig, Config};
+use pint_rs::events::{Event, Events};
+use pint_rs::import;
+use pint_rs::sequence::{format_number, Sequence, DEFAULT_NUMBER_FORMAT};
+use pint_rs::{checks, doctor};
+use pint_rs::{render, Args, EntryPreview, ExchangeRate, Invoice, InvoiceBuilder, RenderOptions};
+use std::collections::BTreeMap;
 use std::error::Error;
+use std::io::Write;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args = Args::parse();
-    let invoice: Invoice = InvoiceBuilder::new(&args).import_csv(&args.file)?.build();
+    let args = Args::parse_from(pint_rs::expand_response_files(std::env::args_os())?);
+    if args.doctor {
+        let checks = doctor::run(args.config.as_deref());
+        for check in &checks {
+            println!("{}", check);
+        }
+        if checks.iter().any(|c| c.problem.is_some()) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
-    println!("{}", invoice);
+    let config = Config::load(args.config.as_deref())?;
+    if args.split_by_client {
+        return split_by_client(&args, &config);
+    }
+    let client = args
+        .client
+        .as_deref()
+        .map(|c| config.client(c))
+        .transpose()?;
+    let args = match (&args.client, client) {
+        (Some(name), Some(client)) => args.with_client_defaults(name, client)?,
+        _ => args,
+    };
+
+    let events = Events::new(args.events);
+    let from = match (args.source, &args.file) {
+        (Some(source), _) => source
+            .to_possible_value()
+            .map_or_else(String::new, |v| v.get_name().to_owned()),
+        (None, Some(file)) => file.display().to_string(),
+        (None, None) => String::new(),
+    };
+    events.emit(Event::ImportStarted { from })?;
+    let mut builder = prepare(&args, &config, client, None)?;
+    if args.validate {
+        return validate(&builder, &config);
+    }
+
+    events.emit(Event::EntriesParsed {
+        count: builder.entries().len(),
+    })?;
+    for warning in builder.warnings() {
+        match events.enabled() {
+            true => events.emit(Event::Warning { message: warning })?,
+            false => eprintln!("Warning: {}", warning),
+        }
+    }
+
+    if let Some(count) = args.preview_entries {
+        eprintln!("{}", EntryPreview::new(builder.entries(), count));
+    }
+
+    if let Some(reason) = builder.deferral() {
+        match events.enabled() {
+            true => events.emit(Event::InvoiceDeferred { reason: &reason })?,
+            false => eprintln!("Not invoicing: {}", reason),
+        }
+        return Ok(());
+    }
+
+    let mut sequence = match args.number {
+        true => Some(Sequence::load(args.sequence_file.as_deref())?),
+        false => None,
+    };
+    if let Some(sequence) = &mut sequence {
+        let number = next_number(sequence, &args, args.client.as_deref(), client)?;
+        builder.number_invoice(number);
+    }
+
+    let invoice: Invoice = builder.build();
+    events.emit(Event::InvoiceBuilt {
+        total_time: invoice.total_time(),
+        total: invoice.total(),
+    })?;
+
+    let options = RenderOptions::new(&args, &config)?;
+    let output = render(&invoice, args.format, &options)?;
+    // The number is only used up once there's an invoice to write
+    if let Some(sequence) = &sequence {
+        sequence.save()?;
+    }
+    if events.enabled() {
+        events.emit(Event::ArtifactWritten {
+            format: args.format,
+            content: &String::from_utf8_lossy(&output),
+        })?;
+    } else {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(&output)?;
+        writeln!(stdout)?;
+    }
+
+    if let Some(profitability) = invoice.profitability() {
+        eprintln!("{}", profitability);
+    }
+
+    Ok(())
+}
+
+/// Sets up a builder for a client and imports the entries into it, ready to build
+fn prepare(
+    args: &pint_rs::Args,
+    config: &Config,
+    client: Option<&ClientConfig>,
+    only_client: Option<&str>,
+) -> Result<InvoiceBuilder, Box<dyn Error>> {
+    let mut builder = InvoiceBuilder::new(args);
+    builder.project_settings(&config.project).parties(
+        config.supplier.as_ref(),
+        client.and_then(|c| c.details.as_ref()),
+    );
+    if let Some(client) = client {
+        builder
+            .schedule_rates(&client.rates)
+            .price_tiers(&client.tiers)
+            .add_items(&client.items);
+        if let Some(minimum) = &client.minimum {
+            builder.minimum_charge(minimum);
+        }
+    }
+    if let Some(only_client) = only_client {
+        builder.only_client(only_client);
+    }
+    let today = Local::now().date_naive();
+    let (from, to) = args.date_bounds(today);
+    if args.holiday_multiplier.is_some() {
+        let holidays = config
+            .holidays
+            .as_ref()
+            .ok_or("--holiday-multiplier needs a [holidays] section in the config file")?;
+        let mut dates = holidays.dates.clone();
+        if let Some(country) = &holidays.country {
+            // Without a range, entries could be from last year as much as this
+            let years =
+                from.map_or(today.year() - 1, |d| d.year())..=to.map_or(today.year(), |d| d.year());
+            dates.extend(pint_rs::holidays::public_holidays(country, years)?);
+        }
+        builder.observe_holidays(&dates);
+    }
+    builder
+        .import(args, config)?
+        .check_corrections()
+        .check_archived_projects()
+        .check_project_names();
+
+    // Fetched entries cover the range asked for, files cover the entries billed
+    let period = args
+        .source
+        .is_some()
+        .then(|| import::date_range(from, to, today));
+    builder.date_invoice(args.invoice_date.unwrap_or(today), args.terms, period);
+
+    if let Some(contract) = client.and_then(|c| c.contract.as_ref()) {
+        builder.check_contract(contract);
+    }
+    if let (Some(to), None) = (args.convert_to, args.exchange_rate) {
+        let from = args.currency.unwrap_or_default();
+        builder.convert(ExchangeRate::fetch(from, to, args.rate_date)?);
+    }
+
+    Ok(builder)
+}
+
+/// Issues the client's next invoice number, written in the format asked for
+/// on the command line, in the client's config section, or by default
+fn next_number(
+    sequence: &mut Sequence,
+    args: &pint_rs::Args,
+    name: Option<&str>,
+    client: Option<&ClientConfig>,
+) -> Result<String, Box<dyn Error>> {
+    let format = args
+        .number_format
+        .as_deref()
+        .or(client.and_then(|c| c.number_format.as_deref()))
+        .unwrap_or(DEFAULT_NUMBER_FORMAT);
+    let name = name.unwrap_or_default();
+    let seq = sequence.issue(name);
+
+    let issued = args
+        .invoice_date
+        .unwrap_or_else(|| Local::now().date_naive());
+
+    Ok(format_number(format, name, issued.year(), seq)?)
+}
+
+/// Prints what was read per project and every problem found, exiting with
+/// an error if there were any
+fn validate(builder: &InvoiceBuilder, config: &Config) -> Result<(), Box<dyn Error>> {
+    let mut projects: BTreeMap<&str, (usize, Duration)> = BTreeMap::new();
+    for entry in builder.entries() {
+        let (count, time) = projects
+            .entry(&entry.project)
+            .or_insert((0, Duration::zero()));
+        *count += 1;
+        *time += entry.duration;
+    }
+
+    println!("{:<30} {:>7} {:>10}", "Project", "Entries", "Hours");
+    for (project, (count, time)) in &projects {
+        let hours = time.num_seconds() as f64 / 3600.0;
+        println!("{:<30} {:>7} {:>10.2}", project, count, hours);
+    }
+
+    let problems: Vec<String> = builder
+        .warnings()
+        .iter()
+        .cloned()
+        .chain(checks::suspicious_entries(
+            builder.entries(),
+            &config.project,
+        ))
+        .collect();
+    for problem in &problems {
+        println!("Problem: {}", problem);
+    }
+    if !problems.is_empty() {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Writes an invoice per client named in the entries, using each client's
+/// config section when there is one
+fn split_by_client(args: &pint_rs::Args, config: &Config) -> Result<(), Box<dyn Error>> {
+    let all = prepare(args, config, None, None)?;
+    let unassigned = all.entries().iter().filter(|e| e.client.is_none()).count();
+    if unassigned > 0 {
+        eprintln!(
+            "Warning: {} entries have no client, so they aren't on any invoice",
+            unassigned
+        );
+    }
+    let clients = all.clients();
+    if clients.is_empty() {
+        return Err("No entries name a client (see --client-column)".into());
+    }
+
+    let options = RenderOptions::new(args, config)?;
+    let extension = match args.format {
+        pint_rs::Format::Text => "txt",
+        pint_rs::Format::Json => "json",
+    };
+    let mut sequence = match args.number {
+        true => Some(Sequence::load(args.sequence_file.as_deref())?),
+        false => None,
+    };
+    for name in clients {
+        let client = config.client.get(name);
+        let args = &match client {
+            Some(client) => args.with_client_defaults(name, client)?,
+            None => args.clone(),
+        };
+        let mut builder = prepare(args, config, client, Some(name))?;
+        for warning in builder.warnings() {
+            eprintln!("Warning: {}: {}", name, warning);
+        }
+        if let Some(reason) = builder.deferral() {
+            eprintln!("Not invoicing {}: {}", name, reason);
+            continue;
+        }
+
+        if let Some(sequence) = &mut sequence {
+            let number = next_number(sequence, args, Some(name), client)?;
+            builder.number_invoice(number);
+        }
+
+        let path = format!("invoice-{}.{}", file_name_part(name), extension);
+        let output = render(&builder.build(), args.format, &options)?;
+        std::fs::write(&path, output)
+            .map_err(|err| format!("Unable to write {}: {}", path, err))?;
+        // Saved after each invoice written, so if a later client fails the
+        // numbers already on written invoices aren't issued again
+        if let Some(sequence) = &sequence {
+            sequence.save()?;
+        }
+        eprintln!("Wrote {}", path);
+    }
 
     Ok(())
 }
+
+/// A client name made safe to put in a file name
+fn file_name_part(name: &str) -> String {
+    name.chars()
+        .map(|c| match c.is_alphanumeric() || c == '-' || c == '_' {
+            true => c,
+            false => '-',
+        })
+        .collect()
+}
@@ -0,0 +1,352 @@
+//! Amounts of money, kept as whole minor units (cents, or yen for currencies
+//! without them) so that adding them up is exact and they never print as
+//! `103.99999999999999`.
+
+use anyhow::Result;
+use chrono::{Duration, NaiveDate};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign, Neg, Sub};
+
+/// An ISO 4217 currency, and how its amounts are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Currency {
+    pub code: &'static str,
+    /// Written before the amount, e.g. "$" or "CHF "
+    pub prefix: &'static str,
+    /// Written after the amount, e.g. " kr"
+    pub suffix: &'static str,
+    /// How many digits come after the decimal point
+    pub decimals: u32,
+}
+
+impl Currency {
+    const fn new(code: &'static str, prefix: &'static str, suffix: &'static str) -> Self {
+        Self {
+            code,
+            prefix,
+            suffix,
+            decimals: 2,
+        }
+    }
+
+    const fn without_minor_units(self) -> Self {
+        Self {
+            decimals: 0,
+            ..self
+        }
+    }
+
+    /// The currencies amounts can be in
+    pub const ALL: [Currency; 18] = [
+        Currency::new("AUD", "$", ""),
+        Currency::new("BRL", "R$", ""),
+        Currency::new("CAD", "$", ""),
+        Currency::new("CHF", "CHF ", ""),
+        Currency::new("CNY", "¥", ""),
+        Currency::new("DKK", "", " kr"),
+        Currency::new("EUR", "€", ""),
+        Currency::new("GBP", "£", ""),
+        Currency::new("HKD", "$", ""),
+        Currency::new("INR", "₹", ""),
+        Currency::new("JPY", "¥", "").without_minor_units(),
+        Currency::new("KRW", "₩", "").without_minor_units(),
+        Currency::new("MXN", "$", ""),
+        Currency::new("NOK", "", " kr"),
+        Currency::new("NZD", "$", ""),
+        Currency::new("SEK", "", " kr"),
+        Currency::new("SGD", "$", ""),
+        Currency::new("USD", "$", ""),
+    ];
+
+    fn minor_per_major(&self) -> f64 {
+        10f64.powi(self.decimals as i32)
+    }
+}
+
+impl Default for Currency {
+    /// The dollars invoices were always written in
+    fn default() -> Self {
+        Currency::new("USD", "$", "")
+    }
+}
+
+impl std::str::FromStr for Currency {
+    type Err = anyhow::Error;
+
+    fn from_str(str: &str) -> Result<Self> {
+        Currency::ALL
+            .into_iter()
+            .find(|c| c.code.eq_ignore_ascii_case(str.trim()))
+            .ok_or_else(|| {
+                let codes: Vec<&str> = Currency::ALL.iter().map(|c| c.code).collect();
+                anyhow::anyhow!(
+                    "Unknown currency \"{}\", expected one of {}",
+                    str,
+                    codes.join(", ")
+                )
+            })
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.code)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Currency {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl serde::Serialize for Currency {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code)
+    }
+}
+
+/// An amount of money in whole minor units of its currency
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Money {
+    minor: i64,
+    currency: Currency,
+}
+
+impl Money {
+    /// The amount rounded to the nearest minor unit
+    pub fn new(amount: f64, currency: Currency) -> Self {
+        Self {
+            minor: (amount * currency.minor_per_major()).round() as i64,
+            currency,
+        }
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Self { minor: 0, currency }
+    }
+
+    pub fn from_minor(minor: i64, currency: Currency) -> Self {
+        Self { minor, currency }
+    }
+
+    /// The amount in cents, or whatever the currency's smallest unit is
+    pub fn minor(self) -> i64 {
+        self.minor
+    }
+
+    pub fn currency(self) -> Currency {
+        self.currency
+    }
+
+    /// The amount in major units, for percentages and serialization
+    pub fn as_f64(self) -> f64 {
+        self.minor as f64 / self.currency.minor_per_major()
+    }
+
+    /// What the time comes to at this hourly rate, rounded to the nearest
+    /// minor unit only once the rate has been applied to every second of it
+    pub fn for_time(self, time: Duration) -> Self {
+        let minor = (self.minor as i128 * time.num_seconds() as i128) as f64 / 3600.0;
+        Self {
+            minor: minor.round() as i64,
+            ..self
+        }
+    }
+
+    /// A share of the amount, such as its GST, rounded to the nearest minor unit
+    pub fn times(self, factor: f64) -> Self {
+        Self {
+            minor: (self.minor as f64 * factor).round() as i64,
+            ..self
+        }
+    }
+
+    /// The amount with the currency's symbol where it belongs, e.g. "$95.50"
+    pub fn with_symbol(&self) -> String {
+        let sign = if self.minor < 0 { "-" } else { "" };
+        format!(
+            "{}{}{}{}",
+            sign,
+            self.currency.prefix,
+            Self {
+                minor: self.minor.abs(),
+                ..*self
+            },
+            self.currency.suffix
+        )
+    }
+
+    /// The amount as an hourly rate, leaving off minor units when there are
+    /// none, e.g. "$95/hr"
+    pub fn per_hour(&self) -> String {
+        self.per("hr")
+    }
+
+    /// The amount as a day rate, like an hourly one, e.g. "$800/day"
+    pub fn per_day(&self) -> String {
+        self.per("day")
+    }
+
+    fn per(&self, unit: &str) -> String {
+        match self.minor % self.currency.minor_per_major() as i64 {
+            0 => format!(
+                "{}{}{}/{}",
+                self.currency.prefix,
+                self.as_f64(),
+                self.currency.suffix,
+                unit
+            ),
+            _ => format!("{}/{}", self.with_symbol(), unit),
+        }
+    }
+
+    fn same_currency(self, other: Money) -> Currency {
+        assert_eq!(
+            self.currency, other.currency,
+            "Amounts in different currencies can't be added up"
+        );
+        self.currency
+    }
+}
+
+impl From<f64> for Money {
+    /// The amount in the default currency
+    fn from(amount: f64) -> Self {
+        Money::new(amount, Currency::default())
+    }
+}
+
+impl PartialOrd for Money {
+    /// Only amounts in the same currency compare
+    fn partial_cmp(&self, other: &Money) -> Option<Ordering> {
+        (self.currency == other.currency).then(|| self.minor.cmp(&other.minor))
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money::from_minor(self.minor + other.minor, self.same_currency(other))
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money::from_minor(self.minor - other.minor, self.same_currency(other))
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money::from_minor(-self.minor, self.currency)
+    }
+}
+
+impl fmt::Display for Money {
+    /// The amount without its symbol, to the currency's decimal places and
+    /// padded to the width asked for
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let minor = self.minor.unsigned_abs();
+        let amount = match self.currency.decimals {
+            0 => format!("{}{}", sign, minor),
+            decimals => {
+                let per_major = 10u64.pow(decimals);
+                format!(
+                    "{}{}.{:0width$}",
+                    sign,
+                    minor / per_major,
+                    minor % per_major,
+                    width = decimals as usize
+                )
+            }
+        };
+        f.pad(&amount)
+    }
+}
+
+impl serde::Serialize for Money {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.as_f64())
+    }
+}
+
+/// How much of another currency one unit of an invoice's buys
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct ExchangeRate {
+    #[serde(rename = "currency")]
+    pub to: Currency,
+    pub rate: f64,
+    /// The day the rate was published, when it was looked up
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+}
+
+impl ExchangeRate {
+    /// The amount in the other currency, rounded to its minor units
+    pub fn convert(&self, amount: Money) -> Money {
+        Money::new(amount.as_f64() * self.rate, self.to)
+    }
+
+    /// Looks up the European Central Bank's reference rate on a day, or its
+    /// latest, through the Frankfurter API
+    ///
+    /// Rates are only published on working days, so the one used may be from
+    /// a day or two before.
+    pub fn fetch(from: Currency, to: Currency, date: Option<NaiveDate>) -> Result<Self> {
+        if from == to {
+            return Ok(Self {
+                to,
+                rate: 1.0,
+                date,
+            });
+        }
+
+        #[cfg(feature = "http")]
+        {
+            #[derive(serde::Deserialize)]
+            struct Rates {
+                date: NaiveDate,
+                rates: std::collections::HashMap<String, f64>,
+            }
+
+            let day = date.map_or_else(|| "latest".to_owned(), |d| d.to_string());
+            let url = format!("{}/{}", FRANKFURTER_API_URL, day);
+            let response: Rates =
+                crate::http::get_json(&url, &[], &[("from", from.code), ("to", to.code)])?;
+            let rate = *response.rates.get(to.code).ok_or_else(|| {
+                anyhow::anyhow!("The ECB publishes no rate from {} to {}", from, to)
+            })?;
+
+            Ok(Self {
+                to,
+                rate,
+                date: Some(response.date),
+            })
+        }
+
+        #[cfg(not(feature = "http"))]
+        {
+            let _ = (from, to, date);
+            Err(crate::missing_feature("Looking up exchange rates", "http"))
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+const FRANKFURTER_API_URL: &str = "https://api.frankfurter.app";
@@ -0,0 +1,472 @@
+//! Renders a built [`Invoice`] into the bytes of an output format.
+//!
+//! [`render`] is the only entry point, so embedders that compute their own
+//! invoices can produce the same output as the command line without going
+//! through any of the importers.
+
+use crate::config::Config;
+use crate::{Args, Invoice, Money, Party, TaxLine};
+use anyhow::{Context, Result};
+use chrono::{Locale, NaiveDate};
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[cfg(feature = "jws")]
+mod jws;
+pub mod words;
+
+/// An output format an invoice can be rendered to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// The plain text table printed to the terminal
+    #[default]
+    Text,
+    /// The invoice's figures as a JSON object
+    Json,
+}
+
+/// The narrowest text table, which leaves room for short labels
+const MIN_TEXT_WIDTH: usize = 25;
+
+/// Knobs for the individual renderers, each ignored by the other formats
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderOptions {
+    pub dates: DateOptions,
+    pub text: TextOptions,
+    pub json: JsonOptions,
+    /// Write the total out in words as well, in the date locale's language
+    pub amount_in_words: bool,
+}
+
+/// Command-line flags for the renderers, namespaced by format so that
+/// knobs for different renderers don't collide
+#[derive(clap::Args, Debug, Clone, Default)]
+#[command(next_help_heading = "Rendering")]
+pub struct RenderArgs {
+    /// The width of the text table in characters
+    #[arg(long = "text.width", id = "text_width", value_name = "COLUMNS")]
+    pub text_width: Option<usize>,
+
+    /// Indent the JSON output
+    #[arg(long = "json.pretty", id = "json_pretty")]
+    pub json_pretty: bool,
+
+    /// Write the JSON with sorted keys and no whitespace, so identical invoices are byte-for-byte identical
+    #[arg(
+        long = "json.canonical",
+        id = "json_canonical",
+        conflicts_with = "json_pretty"
+    )]
+    pub json_canonical: bool,
+
+    /// Sign the JSON as a JWS with the key in this file: an Ed25519 PEM private key, or else an HS256 secret
+    #[arg(long = "json.sign-key", id = "json_sign_key", value_name = "PATH")]
+    pub json_sign_key: Option<std::path::PathBuf>,
+
+    /// Also write the total in words, e.g. "Five hundred thirteen dollars and 00/100"
+    #[arg(long)]
+    pub amount_in_words: bool,
+}
+
+/// How dates are written on formats meant for people to read
+///
+/// JSON always uses ISO 8601 dates so it stays machine readable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateOptions {
+    pub locale: Locale,
+    /// A strftime-style format, defaulting to the locale's usual long form
+    pub format: Option<String>,
+}
+
+impl RenderOptions {
+    /// Gathers the rendering flags, falling back to the config file
+    pub fn new(args: &Args, config: &Config) -> Result<Self> {
+        let dates = config.dates.as_ref();
+        let locale = match (args.locale, dates.and_then(|d| d.locale.as_deref())) {
+            (Some(locale), _) => locale,
+            (None, Some(locale)) => crate::parse_locale(locale)
+                .context("Unable to use the locale in the config file")?,
+            (None, None) => DateOptions::default().locale,
+        };
+
+        Ok(Self {
+            dates: DateOptions {
+                locale,
+                format: dates.and_then(|d| d.format.clone()),
+            },
+            text: TextOptions {
+                width: args
+                    .render
+                    .text_width
+                    .unwrap_or(TextOptions::default().width),
+            },
+            json: JsonOptions {
+                pretty: args.render.json_pretty,
+                canonical: args.render.json_canonical,
+                sign_key: args
+                    .render
+                    .json_sign_key
+                    .as_ref()
+                    .map(|path| {
+                        std::fs::read(path).with_context(|| {
+                            format!("Unable to read the signing key {}", path.display())
+                        })
+                    })
+                    .transpose()?,
+            },
+            amount_in_words: args.render.amount_in_words,
+        })
+    }
+}
+
+impl Default for DateOptions {
+    fn default() -> Self {
+        Self {
+            locale: Locale::en_US,
+            format: None,
+        }
+    }
+}
+
+impl DateOptions {
+    pub fn format(&self, date: NaiveDate) -> String {
+        let format = match (&self.format, self.locale) {
+            (Some(format), _) => format.as_str(),
+            (None, Locale::en_US) => "%B %-d, %Y",
+            (None, _) => "%-d %B %Y",
+        };
+
+        date.format_localized(format, self.locale).to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextOptions {
+    /// The width of the table, with amounts in the last ten columns
+    pub width: usize,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self { width: 41 }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonOptions {
+    /// Indent the output instead of writing it on a single line
+    pub pretty: bool,
+    /// Sort object keys and leave out whitespace, taking precedence over `pretty`
+    pub canonical: bool,
+    /// The key to sign the canonical JSON with, which is then written as a JWS
+    pub sign_key: Option<Vec<u8>>,
+}
+
+/// Renders an invoice in the given format
+pub fn render(invoice: &Invoice, format: Format, options: &RenderOptions) -> Result<Vec<u8>> {
+    match format {
+        Format::Text => Ok(text(invoice, options).into_bytes()),
+        Format::Json if options.amount_in_words => {
+            let mut value =
+                serde_json::to_value(invoice).context("Unable to serialize the invoice as JSON")?;
+            if let serde_json::Value::Object(fields) = &mut value {
+                let words = words::amount_in_words(invoice.amount_payable(), options.dates.locale);
+                fields.insert("amount_in_words".to_owned(), words.into());
+            }
+            json(&value, options)
+        }
+        Format::Json => json(invoice, options),
+    }
+}
+
+fn json<T: Serialize>(value: &T, options: &RenderOptions) -> Result<Vec<u8>> {
+    if let Some(key) = &options.json.sign_key {
+        // Verifiers need the exact bytes signed, so only the canonical form is
+        let canonical = serde_json::to_value(value)
+            .and_then(|v| serde_json::to_vec(&v))
+            .context("Unable to serialize the invoice as JSON")?;
+
+        #[cfg(feature = "jws")]
+        return Ok(jws::sign(&canonical, key)?.into_bytes());
+
+        #[cfg(not(feature = "jws"))]
+        {
+            let _ = (canonical, key);
+            return Err(crate::missing_feature("Signing invoices", "jws"));
+        }
+    }
+
+    let json = match (options.json.canonical, options.json.pretty) {
+        // Objects in a `Value` keep their keys sorted
+        (true, _) => serde_json::to_value(value).and_then(|v| serde_json::to_vec(&v)),
+        (false, true) => serde_json::to_vec_pretty(value),
+        (false, false) => serde_json::to_vec(value),
+    };
+    json.context("Unable to serialize the invoice as JSON")
+}
+
+/// Writes a supplier's or client's details a line each
+fn party_lines(output: &mut String, party: &Party) {
+    for line in party.name.iter().chain(&party.address) {
+        output.push_str(&format!("{}\n", line));
+    }
+    if let Some(tax_id) = &party.tax_id {
+        output.push_str(&format!("Tax ID {}\n", tax_id));
+    }
+    if let Some(email) = &party.email {
+        output.push_str(&format!("{}\n", email));
+    }
+}
+
+/// Lays the invoice out as the plain text table printed to the terminal
+pub(crate) fn text(invoice: &Invoice, options: &RenderOptions) -> String {
+    let mut output = String::new();
+    let width = options.text.width.max(MIN_TEXT_WIDTH);
+    let label = width - 11;
+
+    if let Some(supplier) = &invoice.supplier {
+        party_lines(&mut output, supplier);
+        output.push('\n');
+    }
+    if let Some(bill_to) = &invoice.bill_to {
+        output.push_str("Bill to\n");
+        party_lines(&mut output, bill_to);
+        output.push('\n');
+    }
+
+    if let Some(number) = &invoice.number {
+        output.push_str(&format!("{:<15} {}\n", "Invoice number", number));
+    }
+    let dates = &options.dates;
+    if let Some(issued) = invoice.issued {
+        output.push_str(&format!(
+            "{:<15} {}\n",
+            "Invoice date",
+            dates.format(issued)
+        ));
+    }
+    if let Some(due) = invoice.due {
+        output.push_str(&format!("{:<15} {}\n", "Due date", dates.format(due)));
+    }
+    if let Some((from, to)) = invoice.period {
+        output.push_str(&format!(
+            "{:<15} {} – {}\n",
+            "Period",
+            dates.format(from),
+            dates.format(to)
+        ));
+    }
+    if invoice.number.is_some() || invoice.issued.is_some() || invoice.period.is_some() {
+        output.push('\n');
+    }
+
+    // Format the time entries
+    output.push_str(&format!("{:<label$} {:>10}\n", "Project", "Hours"));
+    output.push_str(&format!("{:-<width$}\n", ""));
+    if invoice.rated_lines.is_empty() {
+        for (project, hours) in &invoice.project_hours_logged {
+            output.push_str(&format!("{:<label$} {:>10.2}\n", project, hours));
+            entry_lines(&mut output, invoice, project, options);
+        }
+    } else {
+        for (i, line) in invoice.rated_lines.iter().enumerate() {
+            output.push_str(&format!(
+                "{:<label$} {:>10.2}\n",
+                match line.surcharge {
+                    Some(surcharge) => format!(
+                        "{} at {} {}",
+                        line.project,
+                        line.rate.per_hour(),
+                        surcharge.label()
+                    ),
+                    None => format!("{} at {}", line.project, line.rate.per_hour()),
+                },
+                line.hours
+            ));
+            // A project's entries go under its last rate
+            if invoice.rated_lines[i + 1..]
+                .iter()
+                .all(|l| l.project != line.project)
+            {
+                entry_lines(&mut output, invoice, &line.project, options);
+            }
+        }
+    }
+
+    // Format the totals
+    output.push_str(&format!(
+        "\n{:<label$} {:>10.2}\n\n",
+        "Total Time (h)", invoice.total_time
+    ));
+    if let Some(days) = &invoice.days {
+        output.push_str(&format!(
+            "{:<label$} {:>10}\n",
+            format!("Days ({} full, {} half)", days.full, days.half),
+            days.count()
+        ));
+    }
+    if let Some(overtime) = &invoice.overtime {
+        output.push_str(&format!(
+            "{:<label$} {:>10}\n",
+            format!(
+                "Overtime {:.2}h at {}x",
+                overtime.hours, overtime.multiplier
+            ),
+            overtime.premium
+        ));
+    }
+    if let Some(fee) = invoice.minimum_fee {
+        output.push_str(&format!(
+            "{:<label$} {:>10}\n",
+            "Minimum engagement fee", fee
+        ));
+    }
+    for item in &invoice.items {
+        output.push_str(&format!("{:<label$} {:>10}\n", item.name, item.amount));
+    }
+    let subtotal_label = match (
+        &invoice.days,
+        invoice.rated_lines.is_empty()
+            && invoice.overtime.is_none()
+            && invoice.minimum_fee.is_none()
+            && invoice.items.is_empty(),
+    ) {
+        (Some(days), _) if invoice.minimum_fee.is_none() && invoice.items.is_empty() => {
+            format!("Subtotal at {}", days.rate.per_day())
+        }
+        (None, true) => format!("Subtotal at {}", invoice.pay_rate.per_hour()),
+        _ => "Subtotal".to_owned(),
+    };
+    output.push_str(&format!(
+        "{:<label$} {:>10}\n",
+        subtotal_label, invoice.subtotal
+    ));
+    if !invoice.tax_exempt.is_empty() {
+        let projects: Vec<&str> = invoice.tax_exempt.keys().map(String::as_str).collect();
+        let amount = invoice
+            .tax_exempt
+            .values()
+            .fold(Money::zero(invoice.subtotal.currency()), |total, a| {
+                total + *a
+            });
+        output.push_str(&format!(
+            "{:<label$} {:>10}\n",
+            format!("Tax exempt: {}", projects.join(", ")),
+            amount
+        ));
+    }
+    tax_lines(&mut output, &invoice.taxes, label);
+    let total_label = match invoice.currency {
+        Some(currency) => format!("TOTAL ({})", currency),
+        None => "TOTAL".to_owned(),
+    };
+    output.push_str(&format!("{:<label$} {:>10}\n", total_label, invoice.total));
+    if let Some(withholding) = &invoice.withholding {
+        output.push_str(&format!(
+            "{:<label$} {:>10}\n",
+            format!("Withholding at {}%", withholding.percent),
+            withholding.amount
+        ));
+        output.push_str(&format!(
+            "{:<label$} {:>10}\n",
+            "AMOUNT PAYABLE",
+            invoice.amount_payable()
+        ));
+    }
+    if let Some(reverse_charge) = &invoice.reverse_charge {
+        output.push_str(&format!(
+            "\nVAT ID {}, client's VAT ID {}\n{}\n",
+            reverse_charge.vat_id, reverse_charge.client_vat_id, reverse_charge.statement
+        ));
+    }
+    if let Some(conversion) = &invoice.conversion {
+        let exchange = &conversion.exchange;
+        output.push_str(&format!(
+            "\nIn {} at {}{}\n",
+            exchange.to,
+            exchange.rate,
+            exchange.date.map_or_else(String::new, |d| format!(
+                ", the ECB's rate on {}",
+                dates.format(d)
+            ))
+        ));
+        output.push_str(&format!(
+            "{:<label$} {:>10}\n",
+            "Subtotal", conversion.subtotal
+        ));
+        tax_lines(&mut output, &conversion.taxes, label);
+        output.push_str(&format!(
+            "{:<label$} {:>10}\n",
+            format!("TOTAL ({})", exchange.to),
+            conversion.total
+        ));
+    }
+    if !invoice.courtesy.is_empty() {
+        output.push_str(&format!(
+            "\n{:<label$} {:>10}\n",
+            "Courtesy time, not charged", "Hours"
+        ));
+        output.push_str(&format!("{:-<width$}\n", ""));
+        for (project, hours) in &invoice.courtesy {
+            output.push_str(&format!("{:<label$} {:>10.2}\n", project, hours));
+        }
+    }
+    if !invoice.non_billable.is_empty() {
+        output.push_str(&format!("\n{:<label$} {:>10}\n", "Not billed", "Hours"));
+        output.push_str(&format!("{:-<width$}\n", ""));
+        for (project, hours) in &invoice.non_billable {
+            output.push_str(&format!("{:<label$} {:>10.2}\n", project, hours));
+        }
+    }
+    if let Some(estimate) = &invoice.estimate {
+        output.push_str(&format!(
+            "\nIncludes {:.2}h of estimated time, {}\n",
+            estimate.hours,
+            match estimate.billed_share < 1.0 {
+                true => format!("billed at {}%", estimate.billed_share * 100.0),
+                false => "billed in full".to_owned(),
+            }
+        ));
+    }
+    if options.amount_in_words {
+        output.push_str(&format!(
+            "\n{}\n",
+            words::amount_in_words(invoice.amount_payable(), dates.locale)
+        ));
+    }
+
+    output
+}
+
+fn tax_lines(output: &mut String, taxes: &[TaxLine], label: usize) {
+    for line in taxes {
+        let mut name = format!("{} at {}%", line.tax.name, line.tax.percent);
+        if line.tax.compound {
+            name.push_str(" compounded");
+        }
+        if let Some(number) = &line.tax.registration {
+            name.push_str(&format!(" (No. {})", number));
+        }
+        output.push_str(&format!("{:<label$} {:>10}\n", name, line.amount));
+    }
+}
+
+/// Lists a project's entries, indented under its line, on a detailed invoice
+fn entry_lines(output: &mut String, invoice: &Invoice, project: &str, options: &RenderOptions) {
+    let label = options.text.width.max(MIN_TEXT_WIDTH) - 13;
+    for entry in invoice.entries.get(project).into_iter().flatten() {
+        let description = [
+            entry.date.map(|d| options.dates.format(d)),
+            entry.description.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("  ");
+        output.push_str(&format!(
+            "  {:<label$} {:>10.2}\n",
+            description, entry.hours
+        ));
+    }
+}
@@ -0,0 +1,248 @@
+//! Writes amounts out in words, as some accounts payable departments require
+//! on invoices alongside the figures.
+//!
+//! English and French are supported; other locales fall back to English.
+
+use crate::{Currency, Money};
+use chrono::Locale;
+
+/// The amount in the locale's language, e.g. "Five hundred thirteen dollars
+/// and 00/100", or "Five hundred yen" for a currency without minor units
+pub fn amount_in_words(amount: Money, locale: Locale) -> String {
+    let currency = amount.currency();
+    let per_major = 10u64.pow(currency.decimals);
+    let minor = amount.minor().unsigned_abs();
+    let (major, minor) = (minor / per_major, minor % per_major);
+    let french_locale = format!("{:?}", locale).starts_with("fr_");
+    let (one, many) = unit_names(currency, french_locale);
+
+    let mut words = match french_locale {
+        true => format!("{} {}", french(major), plural(major, one, many)),
+        false => format!("{} {}", english(major), plural(major, one, many)),
+    };
+    if currency.decimals > 0 {
+        words.push_str(&format!(
+            " {} {:0width$}/{}",
+            if french_locale { "et" } else { "and" },
+            minor,
+            per_major,
+            width = currency.decimals as usize
+        ));
+    }
+
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => words,
+    }
+}
+
+/// What one and many of a currency's major unit are called, falling back to
+/// its code for a currency without a name here
+fn unit_names(currency: Currency, french: bool) -> (&'static str, &'static str) {
+    match (currency.code, french) {
+        ("AUD" | "CAD" | "HKD" | "NZD" | "SGD" | "USD", _) => ("dollar", "dollars"),
+        ("BRL", false) => ("real", "reais"),
+        ("BRL", true) => ("réal", "réaux"),
+        ("CHF", _) => ("franc", "francs"),
+        ("CNY", false) => ("yuan", "yuan"),
+        ("CNY", true) => ("yuan", "yuans"),
+        ("DKK" | "NOK", false) => ("krone", "kroner"),
+        ("SEK", false) => ("krona", "kronor"),
+        ("DKK" | "NOK" | "SEK", true) => ("couronne", "couronnes"),
+        ("EUR", _) => ("euro", "euros"),
+        ("GBP", false) => ("pound", "pounds"),
+        ("GBP", true) => ("livre", "livres"),
+        ("INR", false) => ("rupee", "rupees"),
+        ("INR", true) => ("roupie", "roupies"),
+        ("JPY", false) => ("yen", "yen"),
+        ("JPY", true) => ("yen", "yens"),
+        ("KRW", false) => ("won", "won"),
+        ("KRW", true) => ("won", "wons"),
+        ("MXN", _) => ("peso", "pesos"),
+        (code, _) => (code, code),
+    }
+}
+
+fn plural<'a>(count: u64, one: &'a str, many: &'a str) -> &'a str {
+    match count {
+        1 => one,
+        _ => many,
+    }
+}
+
+const ENGLISH_ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const ENGLISH_TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// Enough scales for any `u64`
+const ENGLISH_SCALES: [&str; 7] = [
+    "",
+    "thousand",
+    "million",
+    "billion",
+    "trillion",
+    "quadrillion",
+    "quintillion",
+];
+
+fn english(number: u64) -> String {
+    if number == 0 {
+        return ENGLISH_ONES[0].to_owned();
+    }
+
+    let mut groups = Vec::new();
+    let mut rest = number;
+    for scale in ENGLISH_SCALES {
+        let group = rest % 1000;
+        if group > 0 {
+            groups.push(match scale {
+                "" => english_below_thousand(group),
+                scale => format!("{} {}", english_below_thousand(group), scale),
+            });
+        }
+        rest /= 1000;
+    }
+
+    groups.reverse();
+    groups.join(" ")
+}
+
+fn english_below_thousand(number: u64) -> String {
+    let (hundreds, rest) = (number / 100, number % 100);
+    let rest = match rest {
+        0 => None,
+        1..=19 => Some(ENGLISH_ONES[rest as usize].to_owned()),
+        _ => Some(match rest % 10 {
+            0 => ENGLISH_TENS[(rest / 10) as usize].to_owned(),
+            ones => format!(
+                "{}-{}",
+                ENGLISH_TENS[(rest / 10) as usize],
+                ENGLISH_ONES[ones as usize]
+            ),
+        }),
+    };
+
+    match (hundreds, rest) {
+        (0, Some(rest)) => rest,
+        (hundreds, None) => format!("{} hundred", ENGLISH_ONES[hundreds as usize]),
+        (hundreds, Some(rest)) => format!("{} hundred {}", ENGLISH_ONES[hundreds as usize], rest),
+    }
+}
+
+const FRENCH_ONES: [&str; 20] = [
+    "zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf", "dix", "onze",
+    "douze", "treize", "quatorze", "quinze", "seize", "dix-sept", "dix-huit", "dix-neuf",
+];
+
+const FRENCH_TENS: [&str; 7] = [
+    "",
+    "",
+    "vingt",
+    "trente",
+    "quarante",
+    "cinquante",
+    "soixante",
+];
+
+fn french(number: u64) -> String {
+    if number == 0 {
+        return FRENCH_ONES[0].to_owned();
+    }
+
+    let mut groups = Vec::new();
+    let mut rest = number;
+    for scale in 0..7 {
+        let group = rest % 1000;
+        rest /= 1000;
+        if group == 0 {
+            continue;
+        }
+
+        groups.push(match scale {
+            0 => french_below_thousand(group, true),
+            // "Mille" never takes an "un" or an "s", and numbers before it
+            // don't either
+            1 if group == 1 => "mille".to_owned(),
+            1 => format!("{} mille", french_below_thousand(group, false)),
+            scale => {
+                let (one, many) = [
+                    ("million", "millions"),
+                    ("milliard", "milliards"),
+                    ("billion", "billions"),
+                    ("billiard", "billiards"),
+                    ("trillion", "trillions"),
+                ][scale - 2];
+                format!(
+                    "{} {}",
+                    french_below_thousand(group, true),
+                    plural(group, one, many)
+                )
+            }
+        });
+    }
+
+    groups.reverse();
+    groups.join(" ")
+}
+
+/// Numbers below a thousand, where `last` says whether anything but a
+/// million or more follows, which is when "cents" and "quatre-vingts" keep
+/// their "s"
+fn french_below_thousand(number: u64, last: bool) -> String {
+    let (hundreds, rest) = (number / 100, number % 100);
+    let rest = match rest {
+        0 => None,
+        1..=19 => Some(FRENCH_ONES[rest as usize].to_owned()),
+        20..=69 => Some(match rest % 10 {
+            0 => FRENCH_TENS[(rest / 10) as usize].to_owned(),
+            1 => format!("{} et un", FRENCH_TENS[(rest / 10) as usize]),
+            ones => format!(
+                "{}-{}",
+                FRENCH_TENS[(rest / 10) as usize],
+                FRENCH_ONES[ones as usize]
+            ),
+        }),
+        70 => Some("soixante-dix".to_owned()),
+        71 => Some("soixante et onze".to_owned()),
+        72..=79 => Some(format!("soixante-{}", FRENCH_ONES[(rest - 60) as usize])),
+        80 if last => Some("quatre-vingts".to_owned()),
+        80 => Some("quatre-vingt".to_owned()),
+        _ => Some(format!(
+            "quatre-vingt-{}",
+            FRENCH_ONES[(rest - 80) as usize]
+        )),
+    };
+
+    match (hundreds, rest) {
+        (0, Some(rest)) => rest,
+        (1, None) => "cent".to_owned(),
+        (1, Some(rest)) => format!("cent {}", rest),
+        (hundreds, None) if last => format!("{} cents", FRENCH_ONES[hundreds as usize]),
+        (hundreds, None) => format!("{} cent", FRENCH_ONES[hundreds as usize]),
+        (hundreds, Some(rest)) => format!("{} cent {}", FRENCH_ONES[hundreds as usize], rest),
+    }
+}
@@ -0,0 +1,139 @@
+//! Invoice numbers, counted up per client in a small state file so that no
+//! number is issued twice or skipped.
+//!
+//! The file is `$XDG_DATA_HOME/pint-rs/sequence.json` (usually
+//! `~/.local/share/pint-rs/sequence.json`), or the one given with
+//! `--sequence-file`, and holds the last number issued to each client:
+//!
+//! ```json
+//! {"acme": 7, "": 12}
+//! ```
+//!
+//! Invoices run without `--client` share the sequence under `""`. A run
+//! holds a lock on `sequence.lock` beside it from loading the numbers until
+//! it's done, so runs at the same time take turns instead of issuing the
+//! same number.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// The format invoice numbers are written in unless one is given
+pub const DEFAULT_NUMBER_FORMAT: &str = "{year}-{seq:3}";
+
+/// The last invoice number issued to each client
+#[derive(Debug)]
+pub struct Sequence {
+    path: PathBuf,
+    last: BTreeMap<String, u32>,
+    /// Held until the sequence is dropped
+    _lock: File,
+}
+
+impl Sequence {
+    /// Reads the state file, where a missing one means no numbers were issued
+    /// yet, waiting for any other run using it to finish first
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_owned(),
+            None => Self::default_path()
+                .context("Unable to find the data directory, give --sequence-file")?,
+        };
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Unable to create directory \"{:?}\"", dir))?;
+        }
+        let lock_path = Self::lock_path(&path);
+        let lock = File::create(&lock_path)
+            .with_context(|| format!("Unable to create lock file \"{:?}\"", lock_path))?;
+        lock.lock()
+            .with_context(|| format!("Unable to lock \"{:?}\"", lock_path))?;
+
+        let last = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .with_context(|| format!("Unable to parse sequence file \"{:?}\"", path))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Unable to read sequence file \"{:?}\"", path))
+            }
+        };
+
+        Ok(Self {
+            path,
+            last,
+            _lock: lock,
+        })
+    }
+
+    /// The file locked while a sequence is in use, which is kept apart from
+    /// the state file since that's replaced on every save
+    pub fn lock_path(path: &Path) -> PathBuf {
+        path.with_extension("lock")
+    }
+
+    /// Where the state file is kept when `--sequence-file` isn't given
+    pub fn default_path() -> Option<PathBuf> {
+        let data_dir = std::env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })?;
+
+        Some(data_dir.join("pint-rs").join("sequence.json"))
+    }
+
+    /// Takes the next number for a client, which is only kept once saved
+    pub fn issue(&mut self, client: &str) -> u32 {
+        let last = self.last.entry(client.to_owned()).or_default();
+        *last += 1;
+        *last
+    }
+
+    /// Writes the numbers back, replacing the file in one step so that an
+    /// interrupted write can't lose the sequence
+    pub fn save(&self) -> Result<()> {
+        let temporary = self.path.with_extension("json.tmp");
+        std::fs::write(&temporary, serde_json::to_vec(&self.last)?)
+            .with_context(|| format!("Unable to write sequence file \"{:?}\"", temporary))?;
+        std::fs::rename(&temporary, &self.path)
+            .with_context(|| format!("Unable to replace sequence file \"{:?}\"", self.path))
+    }
+}
+
+/// Writes an invoice number out in a format such as `ACME-{year}-{seq:3}`,
+/// where `{seq:3}` pads the number with zeros to three digits and `{client}`
+/// is the client's name
+pub fn format_number(format: &str, client: &str, year: i32, seq: u32) -> Result<String> {
+    let mut number = String::new();
+    let mut rest = format;
+    while let Some(start) = rest.find('{') {
+        number.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .with_context(|| format!("Number format \"{}\" has an unclosed {{", format))?;
+        let placeholder = &rest[start + 1..start + end];
+        match placeholder.split_once(':') {
+            None if placeholder == "client" => number.push_str(client),
+            None if placeholder == "year" => number.push_str(&year.to_string()),
+            None if placeholder == "seq" => number.push_str(&seq.to_string()),
+            Some(("seq", width)) => {
+                let width: usize = width.trim().parse().with_context(|| {
+                    format!("The width in \"{{{}}}\" isn't a number", placeholder)
+                })?;
+                number.push_str(&format!("{:0width$}", seq, width = width));
+            }
+            _ => anyhow::bail!(
+                "Number format \"{}\" has an unknown {{{}}}, expected {{client}}, {{year}} or {{seq}}",
+                format,
+                placeholder
+            ),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    number.push_str(rest);
+
+    Ok(number)
+}